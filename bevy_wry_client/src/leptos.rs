@@ -0,0 +1,28 @@
+//! Leptos helpers, enabled with the `leptos` feature.
+use leptos::prelude::*;
+use send_wrapper::SendWrapper;
+use serde::de::DeserializeOwned;
+
+use crate::{default_tag, on_with_tag, Error};
+
+/// Signal holding the last `E` sent by Bevy under [default_tag], `None` until the first one.
+/// The listener is removed when the reactive owner is cleaned up.
+pub fn use_bevy_event<E>() -> Result<ReadSignal<Option<E>>, Error>
+where
+    E: DeserializeOwned + Send + Sync + 'static,
+{
+    use_bevy_event_with_tag(default_tag::<E>())
+}
+
+/// Same as [use_bevy_event], listening under `tag`.
+pub fn use_bevy_event_with_tag<E>(tag: &str) -> Result<ReadSignal<Option<E>>, Error>
+where
+    E: DeserializeOwned + Send + Sync + 'static,
+{
+    let (event, set_event) = signal(None);
+    let subscription = on_with_tag(tag, move |e: E| set_event.set(Some(e)))?;
+    let subscription = SendWrapper::new(subscription);
+    on_cleanup(move || drop(subscription));
+
+    Ok(event)
+}
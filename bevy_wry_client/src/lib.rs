@@ -0,0 +1,171 @@
+//! Typed client for wasm UIs embedded in Bevy with `bevy_wry`.
+//!
+//! Wraps the `window.bevy` client injected by `bevy_wry`, so the game and its UI can share
+//! one crate of `serde` message types:
+//! - [send] events registered with `register_incoming_event`
+//! - [invoke] requests registered with `register_request`
+//! - [on] to receive `OutWryEvent`s using default JSON dispatch
+//!
+//! Tags default to the type name without module path, like in `bevy_wry`.
+#[cfg(feature = "leptos")]
+pub mod leptos;
+
+use js_sys::{Function, Promise, JSON};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use thiserror::Error;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("failed to serialize message: {0}")]
+    Serialize(serde_json::Error),
+    #[error("failed to deserialize message: {0}")]
+    Deserialize(serde_json::Error),
+    #[error("failed to convert message between JSON and JS value: {0}")]
+    JsConversion(String),
+    #[error("bevy_wry client is not available: {0}")]
+    ClientUnavailable(String),
+    #[error("request rejected: {0}")]
+    Rejected(String),
+}
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = bevy, js_name = send, catch)]
+    fn js_send(tag: &str, payload: JsValue) -> Result<(), JsValue>;
+
+    #[wasm_bindgen(js_namespace = bevy, js_name = invoke, catch)]
+    fn js_invoke(tag: &str, payload: JsValue) -> Result<Promise, JsValue>;
+
+    #[wasm_bindgen(js_namespace = bevy, js_name = on, catch)]
+    fn js_on(tag: &str, listener: &Closure<dyn FnMut(JsValue)>) -> Result<Function, JsValue>;
+
+    #[wasm_bindgen(js_namespace = bevy, js_name = ready, catch)]
+    fn js_ready() -> Result<(), JsValue>;
+
+    #[wasm_bindgen(js_namespace = bevy, js_name = reportError)]
+    fn js_report_error(error: &str);
+}
+
+/// Default tag of a message type: its name without module path, e.g. `InCommand`.
+/// Matches `bevy_wry::events::default_tag`.
+pub fn default_tag<T>() -> &'static str {
+    let full_name = std::any::type_name::<T>();
+    let without_generics = full_name.split('<').next().unwrap_or(full_name);
+    without_generics
+        .rsplit("::")
+        .next()
+        .unwrap_or(without_generics)
+}
+
+/// Send `event` to Bevy under [default_tag].
+pub fn send<E: Serialize>(event: &E) -> Result<(), Error> {
+    send_with_tag(default_tag::<E>(), event)
+}
+
+/// Send `event` to Bevy under `tag`.
+pub fn send_with_tag<E: Serialize>(tag: &str, event: &E) -> Result<(), Error> {
+    js_send(tag, to_js(event)?).map_err(client_unavailable)
+}
+
+/// Send `request` to Bevy under [default_tag] and wait for the response.
+pub async fn invoke<R: Serialize, T: DeserializeOwned>(request: &R) -> Result<T, Error> {
+    invoke_with_tag(default_tag::<R>(), request).await
+}
+
+/// Send `request` to Bevy under `tag` and wait for the response.
+pub async fn invoke_with_tag<R: Serialize, T: DeserializeOwned>(
+    tag: &str,
+    request: &R,
+) -> Result<T, Error> {
+    let promise = js_invoke(tag, to_js(request)?).map_err(client_unavailable)?;
+    let response = JsFuture::from(promise)
+        .await
+        .map_err(|e| Error::Rejected(js_error_message(&e)))?;
+    from_js(&response)
+}
+
+/// Call `listener` for every `E` sent by Bevy under [default_tag].
+/// The listener is removed when returned [Subscription] is dropped.
+pub fn on<E: DeserializeOwned + 'static>(
+    listener: impl FnMut(E) + 'static,
+) -> Result<Subscription, Error> {
+    on_with_tag(default_tag::<E>(), listener)
+}
+
+/// Call `listener` for every `E` sent by Bevy under `tag`.
+/// Messages that can't be deserialized are reported with `bevy.reportError`.
+pub fn on_with_tag<E: DeserializeOwned + 'static>(
+    tag: &str,
+    mut listener: impl FnMut(E) + 'static,
+) -> Result<Subscription, Error> {
+    let tag_name = tag.to_string();
+    let closure =
+        Closure::<dyn FnMut(JsValue)>::new(move |payload: JsValue| match from_js::<E>(&payload) {
+            Ok(event) => listener(event),
+            Err(e) => js_report_error(&format!("failed to handle '{tag_name}': {e}")),
+        });
+    let unsubscribe = js_on(tag, &closure).map_err(client_unavailable)?;
+
+    Ok(Subscription {
+        unsubscribe,
+        _closure: closure,
+    })
+}
+
+/// Tell Bevy the page is ready to receive events.
+/// Needed only when the page has `<meta name="bevy-wry-manual-ready">`.
+pub fn ready() -> Result<(), Error> {
+    js_ready().map_err(client_unavailable)
+}
+
+/// Listener registered with [on]. Dropping it removes the listener.
+pub struct Subscription {
+    unsubscribe: Function,
+    _closure: Closure<dyn FnMut(JsValue)>,
+}
+
+impl Subscription {
+    /// Keep the listener registered for the lifetime of the page.
+    pub fn forget(self) {
+        std::mem::forget(self);
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        let _ = self.unsubscribe.call0(&JsValue::NULL);
+    }
+}
+
+/// Messages are exchanged as JSON, exactly like `serde_json` does it on the Bevy side.
+fn to_js<T: Serialize>(value: &T) -> Result<JsValue, Error> {
+    let json = serde_json::to_string(value).map_err(Error::Serialize)?;
+    JSON::parse(&json).map_err(js_conversion)
+}
+
+fn from_js<T: DeserializeOwned>(value: &JsValue) -> Result<T, Error> {
+    let json: String = if value.is_undefined() {
+        "null".to_string()
+    } else {
+        JSON::stringify(value).map_err(js_conversion)?.into()
+    };
+    serde_json::from_str(&json).map_err(Error::Deserialize)
+}
+
+fn js_error_message(error: &JsValue) -> String {
+    match error.dyn_ref::<js_sys::Error>() {
+        Some(error) => error.message().into(),
+        None => error.as_string().unwrap_or_else(|| format!("{error:?}")),
+    }
+}
+
+fn client_unavailable(error: JsValue) -> Error {
+    Error::ClientUnavailable(js_error_message(&error))
+}
+
+fn js_conversion(error: JsValue) -> Error {
+    Error::JsConversion(js_error_message(&error))
+}
@@ -0,0 +1,461 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Attribute, Data, DeriveInput, Fields, LitStr};
+
+/// Derive `bevy_wry::events::OutWryEvent`.
+///
+/// Without attributes the event uses default JSON dispatch. Supported attributes:
+/// - `#[wry(tag = "name")]` on the type - tag used by default dispatch
+/// - `#[wry(js = "fn")]` on a struct or enum variant - call JS function `fn`,
+///   passing every field as JSON encoded argument, in declaration order
+/// - `#[wry(js = "fn")]` on a field - call JS function `fn` with this field only
+/// - `#[wry(skip)]` on a field - don't pass this field to the JS function, requires `js`
+///
+/// Enum variants without `js` attributes fall back to default JSON dispatch.
+#[proc_macro_derive(OutWryEvent, attributes(wry))]
+pub fn derive_out_wry_event(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[derive(Default)]
+struct WryAttrs {
+    js: Option<LitStr>,
+    tag: Option<LitStr>,
+    skip: bool,
+}
+
+fn parse_attrs(attrs: &[Attribute]) -> syn::Result<WryAttrs> {
+    let mut wry_attrs = WryAttrs::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("wry")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("js") {
+                let js: LitStr = meta.value()?.parse()?;
+                validate_js_function(&js)?;
+                wry_attrs.js = Some(js);
+            } else if meta.path.is_ident("tag") {
+                wry_attrs.tag = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("skip") {
+                wry_attrs.skip = true;
+            } else {
+                return Err(meta.error("expected `js`, `tag` or `skip`"));
+            }
+
+            Ok(())
+        })?;
+    }
+
+    Ok(wry_attrs)
+}
+
+/// Function name is pasted into the script, so allow only (dotted) JS identifiers.
+fn validate_js_function(js: &LitStr) -> syn::Result<()> {
+    let value = js.value();
+    let is_identifier = |part: &str| {
+        let mut chars = part.chars();
+        chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+    };
+
+    if value.split('.').all(is_identifier) {
+        Ok(())
+    } else {
+        Err(syn::Error::new(
+            js.span(),
+            "expected JS function name, e.g. `showButton` or `ui.showButton`",
+        ))
+    }
+}
+
+/// Expression building script that calls JS functions for `fields` bound to `bindings`.
+fn calls(
+    js: Option<&LitStr>,
+    fields: &Fields,
+    bindings: &[syn::Ident],
+) -> syn::Result<TokenStream2> {
+    let mut calls = Vec::new();
+    let mut args = Vec::new();
+    for (field, binding) in fields.iter().zip(bindings) {
+        let field_attrs = parse_attrs(&field.attrs)?;
+        if field_attrs.skip {
+            continue;
+        }
+
+        let arg = quote! { ::bevy_wry::events::js_arg(#binding) };
+        match field_attrs.js {
+            Some(field_js) => {
+                calls.push(quote! { ::bevy_wry::events::js_call(#field_js, &[#arg]) })
+            }
+            None => args.push(arg),
+        }
+    }
+
+    if let Some(js) = js {
+        calls.insert(
+            0,
+            quote! { ::bevy_wry::events::js_call(#js, &[#(#args),*]) },
+        );
+    }
+
+    if calls.is_empty() {
+        return Ok(quote! { String::new() });
+    }
+
+    Ok(quote! { [#(#calls),*].concat() })
+}
+
+/// Validates attributes of every field, so they are not ignored without `js`.
+fn has_js(js: Option<&LitStr>, fields: &Fields) -> syn::Result<bool> {
+    let mut has_js = js.is_some();
+    let mut skipped = None;
+    for field in fields.iter() {
+        let field_attrs = parse_attrs(&field.attrs)?;
+        if field_attrs.tag.is_some() {
+            return Err(syn::Error::new(
+                field.span(),
+                "`tag` is not supported on fields",
+            ));
+        }
+
+        if field_attrs.skip {
+            skipped.get_or_insert(field.span());
+        }
+
+        has_js |= field_attrs.js.is_some();
+    }
+
+    // Default dispatch serializes every field, `skip` applies only to JS calls
+    match skipped {
+        Some(span) if !has_js => Err(syn::Error::new(
+            span,
+            "`skip` requires `js` on the type, enum variant or another field",
+        )),
+        _ => Ok(has_js),
+    }
+}
+
+fn bindings(fields: &Fields) -> Vec<syn::Ident> {
+    (0..fields.len())
+        .map(|i| format_ident!("__field{i}"))
+        .collect()
+}
+
+/// Pattern destructuring `fields` into `bindings`.
+fn pattern(fields: &Fields, bindings: &[syn::Ident]) -> TokenStream2 {
+    match fields {
+        Fields::Named(named) => {
+            let names = named.named.iter().map(|field| &field.ident);
+            quote! { { #(#names: #bindings),* } }
+        }
+        Fields::Unnamed(_) => quote! { ( #(#bindings),* ) },
+        Fields::Unit => quote! {},
+    }
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let container_attrs = parse_attrs(&input.attrs)?;
+    if container_attrs.skip {
+        return Err(syn::Error::new(
+            name.span(),
+            "`skip` is supported only on fields",
+        ));
+    }
+
+    let default_script = quote! {
+        ::bevy_wry::events::dispatch_script(<Self as ::bevy_wry::events::OutWryEvent>::tag(), self)
+    };
+
+    let to_script = match &input.data {
+        Data::Struct(data) => {
+            if has_js(container_attrs.js.as_ref(), &data.fields)? {
+                let bindings = bindings(&data.fields);
+                let pattern = pattern(&data.fields, &bindings);
+                let calls = calls(container_attrs.js.as_ref(), &data.fields, &bindings)?;
+                Some(quote! {
+                    let Self #pattern = self;
+                    #calls
+                })
+            } else {
+                None
+            }
+        }
+        Data::Enum(data) => {
+            if container_attrs.js.is_some() {
+                return Err(syn::Error::new(
+                    name.span(),
+                    "put `js` on enum variants instead of the enum",
+                ));
+            }
+
+            let mut arms = Vec::new();
+            let mut any_js = false;
+            for variant in &data.variants {
+                let variant_attrs = parse_attrs(&variant.attrs)?;
+                if variant_attrs.tag.is_some() || variant_attrs.skip {
+                    return Err(syn::Error::new(
+                        variant.span(),
+                        "only `js` is supported on enum variants",
+                    ));
+                }
+
+                let variant_name = &variant.ident;
+                let bindings = bindings(&variant.fields);
+                let pattern = pattern(&variant.fields, &bindings);
+                if has_js(variant_attrs.js.as_ref(), &variant.fields)? {
+                    any_js = true;
+                    let calls = calls(variant_attrs.js.as_ref(), &variant.fields, &bindings)?;
+                    arms.push(quote! { Self::#variant_name #pattern => #calls, });
+                } else {
+                    let pattern = match &variant.fields {
+                        Fields::Named(_) => quote! { { .. } },
+                        Fields::Unnamed(_) => quote! { (..) },
+                        Fields::Unit => quote! {},
+                    };
+                    arms.push(quote! { Self::#variant_name #pattern => #default_script, });
+                }
+            }
+
+            any_js.then(|| {
+                quote! {
+                    match self {
+                        #(#arms)*
+                    }
+                }
+            })
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new(
+                name.span(),
+                "OutWryEvent can't be derived for unions",
+            ))
+        }
+    };
+
+    let tag = container_attrs.tag.map(|tag| {
+        quote! {
+            fn tag() -> &'static str {
+                #tag
+            }
+        }
+    });
+    let to_script = to_script.map(|body| {
+        quote! {
+            #[allow(unused_variables)]
+            fn to_script(&self) -> String {
+                #body
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::bevy_wry::events::OutWryEvent for #name #ty_generics #where_clause {
+            #tag
+            #to_script
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    fn expand_ok(input: DeriveInput) -> String {
+        expand(input).unwrap().to_string()
+    }
+
+    fn expand_err(input: DeriveInput) -> String {
+        expand(input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn struct_without_attributes_uses_defaults() {
+        let expanded = expand_ok(parse_quote! {
+            struct Score(u32);
+        });
+        let expected = quote! {
+            impl ::bevy_wry::events::OutWryEvent for Score {}
+        };
+        assert_eq!(expanded, expected.to_string());
+    }
+
+    #[test]
+    fn struct_fields_are_passed_in_order() {
+        let expanded = expand_ok(parse_quote! {
+            #[wry(js = "ui.setHealth")]
+            struct SetHealth {
+                current: f32,
+                #[wry(skip)]
+                regen: f32,
+                max: f32,
+                #[wry(js = "ui.setName")]
+                name: String,
+            }
+        });
+        let expected = quote! {
+            impl ::bevy_wry::events::OutWryEvent for SetHealth {
+                #[allow(unused_variables)]
+                fn to_script(&self) -> String {
+                    let Self { current: __field0, regen: __field1, max: __field2, name: __field3 } = self;
+                    [
+                        ::bevy_wry::events::js_call(
+                            "ui.setHealth",
+                            &[
+                                ::bevy_wry::events::js_arg(__field0),
+                                ::bevy_wry::events::js_arg(__field2)
+                            ]
+                        ),
+                        ::bevy_wry::events::js_call(
+                            "ui.setName",
+                            &[::bevy_wry::events::js_arg(__field3)]
+                        )
+                    ]
+                    .concat()
+                }
+            }
+        };
+        assert_eq!(expanded, expected.to_string());
+    }
+
+    #[test]
+    fn enum_variants_without_js_use_default_dispatch() {
+        let expanded = expand_ok(parse_quote! {
+            #[wry(tag = "command")]
+            enum OutCommand {
+                #[wry(js = "showButton")]
+                ShowButton,
+                Inventory(Vec<String>),
+                #[wry(js = "$.move")]
+                Move(f32, f32),
+            }
+        });
+        let expected = quote! {
+            impl ::bevy_wry::events::OutWryEvent for OutCommand {
+                fn tag() -> &'static str {
+                    "command"
+                }
+
+                #[allow(unused_variables)]
+                fn to_script(&self) -> String {
+                    match self {
+                        Self::ShowButton => [::bevy_wry::events::js_call("showButton", &[])].concat(),
+                        Self::Inventory(..) => ::bevy_wry::events::dispatch_script(
+                            <Self as ::bevy_wry::events::OutWryEvent>::tag(),
+                            self
+                        ),
+                        Self::Move(__field0, __field1) => [::bevy_wry::events::js_call(
+                            "$.move",
+                            &[
+                                ::bevy_wry::events::js_arg(__field0),
+                                ::bevy_wry::events::js_arg(__field1)
+                            ]
+                        )]
+                        .concat(),
+                    }
+                }
+            }
+        };
+        assert_eq!(expanded, expected.to_string());
+    }
+
+    #[test]
+    fn invalid_js_identifiers_are_rejected() {
+        let expected = "expected JS function name, e.g. `showButton` or `ui.showButton`";
+        for js in [
+            "alert(1); showButton",
+            "1button",
+            "ui..show",
+            "ui.",
+            "",
+            "show-button",
+        ] {
+            let js = LitStr::new(js, proc_macro2::Span::call_site());
+            let error = expand_err(parse_quote! {
+                #[wry(js = #js)]
+                struct ShowButton;
+            });
+            assert_eq!(error, expected, "{}", js.value());
+        }
+    }
+
+    #[test]
+    fn malformed_attributes_are_rejected() {
+        assert_eq!(
+            expand_err(parse_quote! {
+                #[wry(script = "showButton")]
+                struct ShowButton;
+            }),
+            "expected `js`, `tag` or `skip`"
+        );
+        assert_eq!(
+            expand_err(parse_quote! {
+                #[wry(js)]
+                struct ShowButton;
+            }),
+            "expected `=`"
+        );
+        assert_eq!(
+            expand_err(parse_quote! {
+                #[wry(skip)]
+                struct ShowButton;
+            }),
+            "`skip` is supported only on fields"
+        );
+        assert_eq!(
+            expand_err(parse_quote! {
+                struct SetName {
+                    #[wry(tag = "name")]
+                    name: String,
+                }
+            }),
+            "`tag` is not supported on fields"
+        );
+        assert_eq!(
+            expand_err(parse_quote! {
+                struct SetHealth {
+                    current: f32,
+                    #[wry(skip)]
+                    max: f32,
+                }
+            }),
+            "`skip` requires `js` on the type, enum variant or another field"
+        );
+        assert_eq!(
+            expand_err(parse_quote! {
+                enum OutCommand {
+                    #[wry(js = "showButton")]
+                    ShowButton,
+                    SetHealth(f32, #[wry(skip)] f32),
+                }
+            }),
+            "`skip` requires `js` on the type, enum variant or another field"
+        );
+        assert_eq!(
+            expand_err(parse_quote! {
+                #[wry(js = "ui.command")]
+                enum OutCommand {
+                    ShowButton,
+                }
+            }),
+            "put `js` on enum variants instead of the enum"
+        );
+        assert_eq!(
+            expand_err(parse_quote! {
+                enum OutCommand {
+                    #[wry(skip)]
+                    ShowButton,
+                }
+            }),
+            "only `js` is supported on enum variants"
+        );
+    }
+}
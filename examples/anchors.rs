@@ -3,7 +3,7 @@ use bevy::color::palettes::css::PURPLE;
 use bevy::prelude::*;
 use bevy_wry::components::webview::{Initialized, WebViewBundleBuilder};
 use bevy_wry::components::Anchor;
-use bevy_wry::{register_incoming_event, BevyWryPlugin};
+use bevy_wry::{BevyWryPlugin, WryAppExt};
 use wry::dpi::{LogicalPosition, LogicalSize};
 
 const WEBVIEW_NAME: &str = "MAIN_WEBVIEW";
@@ -21,9 +21,8 @@ fn main() {
         .insert_resource(ClearColor(Color::Srgba(PURPLE)))
         .add_event::<NextAnchor>()
         .add_plugins(DefaultPlugins)
-        .add_plugins(BevyWryPlugin::new(|app| {
-            register_incoming_event::<NextAnchor>(app);
-        }))
+        .add_plugins(BevyWryPlugin::default())
+        .add_wry_incoming::<NextAnchor>()
         .add_systems(Startup, setup)
         .add_observer(next_anchor)
         .run();
@@ -49,9 +48,8 @@ fn setup(mut commands: Commands) {
         </body>
         <script>
           function nextAnchor() {
-            <!-- null will be deserialized to NextAnchor -->
-            console.log("ASDF");
-            window.ipc.postMessage("null");
+            // Missing payload will be deserialized to NextAnchor
+            window.bevy.send("NextAnchor");
           }
         </script>
       </html>
@@ -4,7 +4,6 @@ use bevy::prelude::*;
 use bevy_wry::components::webview::WebViewBundleBuilder;
 use bevy_wry::events::OutWryEvent;
 use bevy_wry::{register_incoming_event, register_out_event, BevyWryPlugin};
-use std::env;
 
 const WEBVIEW_NAME: &str = "MAIN_WEBVIEW";
 
@@ -16,23 +15,20 @@ enum InCommand {
 }
 
 /// Command send to webview
-#[derive(Event, Clone, serde::Serialize)]
+#[derive(Event, Clone, serde::Serialize, OutWryEvent)]
 enum OutCommand {
+    /// Calls `window.showButton()` defined in the page
+    #[wry(js = "showButton")]
     ShowButton,
 }
 
-impl OutWryEvent for OutCommand {
-    fn to_script(&self) -> String {
-        match self {
-            OutCommand::ShowButton => "showButton()".to_string(),
-        }
-    }
-}
-
 fn main() {
     App::new()
         .insert_resource(ClearColor(Color::Srgba(PURPLE)))
-        .add_plugins(DefaultPlugins)
+        .add_plugins(DefaultPlugins.set(AssetPlugin {
+            file_path: "examples/web".to_string(),
+            ..default()
+        }))
         .add_plugins(BevyWryPlugin::new(|app| {
             register_incoming_event::<InCommand>(app);
             register_out_event::<OutCommand>(app);
@@ -53,13 +49,11 @@ fn setup(mut commands: Commands) {
         Transform::from_translation(Vec3::new(-50.0, -50.0, 0.0)),
     ));
 
-    // bevy_wry needs absolute path to files for now
-    let manifest_path = env::var("CARGO_MANIFEST_DIR").unwrap();
-    let ui_path = format!("file://{manifest_path}/examples/web/index.html");
+    // Served from `examples/web` asset directory via `bevy://assets/ui.html`
     commands.spawn(
         WebViewBundleBuilder::new(WEBVIEW_NAME)
             .with_transparent(true)
-            .with_url(ui_path)
+            .with_asset("ui.html")
             .build(),
     );
 }
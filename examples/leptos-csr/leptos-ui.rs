@@ -1,33 +1,47 @@
+#[path = "messages.rs"]
+mod messages;
+
 use bevy::prelude::*;
 use bevy_wry::components::webview::WebViewBundleBuilder;
-use bevy_wry::BevyWryPlugin;
+use bevy_wry::events::OutWryEvent;
+use bevy_wry::rust_embed::RustEmbed;
+use bevy_wry::{register_incoming_event, register_out_event, BevyWryPlugin, WryAppExt};
+use messages::{ButtonClicked, Greeting};
 use std::env;
-use std::process::{Child, Command, Stdio};
+use std::process::{Command, Stdio};
+
+/// Trunk output. Read from disk in debug builds, embedded in the binary in release builds.
+#[derive(RustEmbed)]
+#[folder = "examples/leptos-csr/ui/dist"]
+#[crate_path = "bevy_wry::rust_embed"]
+struct Ui;
+
+/// Received in the UI with `bevy_wry_client::leptos::use_bevy_event::<Greeting>()`
+impl OutWryEvent for Greeting {}
 
 fn setup(mut commands: Commands) {
     commands.spawn(
         WebViewBundleBuilder::new("WV_NAME")
             .with_transparent(true)
-            .with_url("http://localhost:8080".to_string())
+            .with_bundle("ui", "index.html")
             .build(),
     );
 }
 
-struct CommandGuard(Child);
-impl Drop for CommandGuard {
-    fn drop(&mut self) {
-        match self.0.kill() {
-            Ok(_) => println!("Trunk closed"),
-            Err(e) => println!("Failed to kill child process: {e}"),
-        }
-    }
+fn greet(trigger: Trigger<ButtonClicked>, mut commands: Commands) {
+    let ButtonClicked { count } = trigger.event();
+    let greeting = Greeting {
+        message: format!("Hello from Bevy! Button was clicked {count} times"),
+    };
+    commands.trigger_targets(greeting, trigger.entity());
 }
 
 fn main() {
     let manifest_path = env::var("CARGO_MANIFEST_DIR").unwrap();
-    let ui_path = format!("{manifest_path}/examples/leptos/ui");
+    let ui_path = format!("{manifest_path}/examples/leptos-csr/ui");
 
-    // Use trunk to build `ui` which is a leptos project
+    // Use trunk to build `ui` which is a leptos project. No server is needed,
+    // `dist` is served as `bevy://ui/` bundle
     Command::new("trunk")
         .arg("build")
         .current_dir(&ui_path)
@@ -36,17 +50,14 @@ fn main() {
         .output()
         .unwrap();
 
-    // Use trunk to serve `ui` which is a leptos project
-    let o = Command::new("trunk")
-        .arg("serve")
-        .current_dir(ui_path)
-        .spawn()
-        .unwrap();
-
-    let _g = CommandGuard(o);
     App::new()
         .add_plugins(DefaultPlugins)
-        .add_plugins(BevyWryPlugin::new(|_| {}))
+        .add_plugins(BevyWryPlugin::new(|app| {
+            register_incoming_event::<ButtonClicked>(app);
+            register_out_event::<Greeting>(app);
+        }))
+        .add_wry_ui_bundle::<Ui>("ui")
         .add_systems(Startup, setup)
+        .add_observer(greet)
         .run();
 }
@@ -0,0 +1,16 @@
+//! Message types shared by the game (`leptos-ui.rs`) and its UI (`ui/src/main.rs`).
+use serde::{Deserialize, Serialize};
+
+/// Sent from the UI when the button is clicked.
+#[cfg_attr(not(target_arch = "wasm32"), derive(bevy::prelude::Event))]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ButtonClicked {
+    pub count: u32,
+}
+
+/// Sent from the game in response to [ButtonClicked].
+#[cfg_attr(not(target_arch = "wasm32"), derive(bevy::prelude::Event))]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Greeting {
+    pub message: String,
+}
@@ -1,5 +1,32 @@
+#[path = "../../messages.rs"]
+mod messages;
+
+use bevy_wry_client::leptos::use_bevy_event;
 use leptos::prelude::*;
+use messages::{ButtonClicked, Greeting};
+
+#[component]
+fn App() -> impl IntoView {
+    let (count, set_count) = signal(0);
+    let greeting = use_bevy_event::<Greeting>().expect("page is embedded with bevy_wry");
+
+    let on_click = move |_| {
+        set_count.update(|count| *count += 1);
+        let event = ButtonClicked {
+            count: count.get_untracked(),
+        };
+        if let Err(e) = bevy_wry_client::send(&event) {
+            leptos::logging::error!("{e}");
+        }
+    };
+
+    view! {
+        <p>"Hello world!"</p>
+        <button on:click=on_click>"Click me: " {count}</button>
+        <p>{move || greeting.get().map(|greeting| greeting.message)}</p>
+    }
+}
 
 fn main() {
-    leptos::mount::mount_to_body(|| view! { <p>"Hello world!"</p> });
+    leptos::mount::mount_to_body(App);
 }
@@ -0,0 +1,46 @@
+//! JavaScript client injected into every webview as `window.bevy`.
+//!
+//! Pages shouldn't use `window.ipc` directly - the client wraps it into the following API:
+//! - `bevy.version` - version of bevy_wry that injected the client
+//! - `bevy.send(type, payload)` - send event registered with [crate::register_incoming_event]
+//! - `bevy.invoke(type, payload)` - send request registered with [crate::register_request],
+//!   returns a `Promise` settled by the [crate::events::request::WryRequest] observer
+//! - `bevy.on(type, listener)` / `bevy.off(type, listener)` - listen for
+//!   [crate::events::OutWryEvent]s, `bevy.on` returns a function removing the listener.
+//!   Events are also dispatched as `bevy:<type>` DOM `CustomEvent`s on `window`
+//! - `bevy.handle(name, handler)` - register handler called by [crate::events::query::JsQuery::call]
+//! - `bevy.ready()` - tell Bevy the page can receive events, triggering
+//!   [crate::events::lifecycle::WebViewReady]. Called automatically on `DOMContentLoaded`,
+//!   unless the page has `<meta name="bevy-wry-manual-ready">`
+//! - `bevy.reportError(error)` - trigger [crate::events::lifecycle::WebViewJsError].
+//!   Uncaught errors and unhandled rejections are reported automatically
+//!
+//! Members starting with `__` are internal and may change between versions.
+//!
+//! Frontends written for Tauri can use [TAURI_SHIM_SCRIPT], enabled with
+//! [crate::BevyWryPlugin::with_tauri_shim]. It provides `window.__TAURI__` (Tauri 2
+//! `withGlobalTauri` API and Tauri 1 aliases) and `window.__TAURI_INTERNALS__` used by
+//! `@tauri-apps/api`:
+//! - `invoke(cmd, args)` - `bevy.invoke(cmd, args)`, register the command with
+//!   [crate::register_request_with_tag] using the Tauri command name, e.g. `"get_inventory"`.
+//!   Events registered with [crate::register_incoming_event] resolve with `null`
+//! - `listen(event, handler)` / `once(event, handler)` - `bevy.on(event, ...)`, handler
+//!   receives `{ event, id, payload }` for every [crate::events::OutWryEvent] dispatched
+//!   under tag `event`
+//! - `emit(event, payload)` - `bevy.send(event, payload)`
+//!
+//! Other Tauri APIs and plugins are not available.
+
+/// Version of the client, sent by the page with the ready handshake.
+pub const CLIENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Tauri compatibility shim, evaluated after [CLIENT_SCRIPT].
+pub const TAURI_SHIM_SCRIPT: &str = include_str!("js/tauri.js");
+
+/// Client script, evaluated before the page scripts run.
+pub const CLIENT_SCRIPT: &str = concat!(
+    include_str!("js/bevy.js"),
+    "(\"",
+    env!("CARGO_PKG_VERSION"),
+    "\");"
+);
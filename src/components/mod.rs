@@ -1,4 +1,5 @@
 pub mod bounds;
+pub mod navigation;
 pub mod webview;
 
 pub use bounds::Anchor;
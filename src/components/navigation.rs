@@ -0,0 +1,216 @@
+use std::sync::{Arc, RwLock};
+
+use bevy::prelude::*;
+
+type NavigationFilter = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// Decides which urls the webview can navigate to. Blocked navigations are triggered as
+/// [crate::events::lifecycle::NavigationBlocked] on the webview entity.
+///
+/// Webviews without this component can navigate anywhere. Policy is checked by wry
+/// navigation handler, so it has to decide synchronously - observers can't allow or
+/// block navigation, use [NavigationPolicy::with_filter] instead.
+///
+/// Example
+/// ```rust
+/// use bevy_wry::components::navigation::NavigationPolicy;
+///
+/// // Allow the UI server and `file:` urls, open other links in the system browser
+/// let policy = NavigationPolicy::allow(["http://localhost:8080", "file:"]).with_open_external(true);
+/// ```
+#[derive(Component, Clone, Default)]
+pub struct NavigationPolicy {
+    /// Allowed origins (`https://example.com`) and schemes (`file:`). `None` allows all urls
+    allowed: Option<Vec<String>>,
+    filter: Option<NavigationFilter>,
+    open_external: bool,
+}
+
+impl NavigationPolicy {
+    /// Allow every url. Default.
+    pub fn allow_all() -> Self {
+        Self::default()
+    }
+
+    /// Allow only urls matching one of `allowed` origins (e.g. `https://example.com`)
+    /// or schemes (e.g. `file:`). Remember to allow the url of [crate::components::webview::Source].
+    /// Trailing `/` of origins is ignored, `https://example.com/` allows its every path.
+    pub fn allow<S: Into<String>>(allowed: impl IntoIterator<Item = S>) -> Self {
+        let allowed = allowed
+            .into_iter()
+            .map(|entry| entry.into().trim_end_matches('/').to_string())
+            .collect();
+        Self {
+            allowed: Some(allowed),
+            ..Default::default()
+        }
+    }
+
+    /// Block every url, except `about:blank` used by html sources.
+    pub fn deny_all() -> Self {
+        Self::allow(Vec::<String>::new())
+    }
+
+    /// Additionally require `filter` to return `true` for the url.
+    pub fn with_filter(mut self, filter: impl Fn(&str) -> bool + Send + Sync + 'static) -> Self {
+        self.filter = Some(Arc::new(filter));
+        self
+    }
+
+    /// Open blocked `http` and `https` urls in the system browser. Other urls (`file:`,
+    /// custom protocols) are only reported as blocked. Default: false
+    pub fn with_open_external(mut self, open_external: bool) -> Self {
+        self.open_external = open_external;
+        self
+    }
+
+    pub fn open_external(&self) -> bool {
+        self.open_external
+    }
+
+    pub fn is_allowed(&self, url: &str) -> bool {
+        if url == "about:blank" {
+            return true;
+        }
+
+        let allowed = match &self.allowed {
+            Some(allowed) => allowed.iter().any(|entry| matches_entry(url, entry)),
+            None => true,
+        };
+
+        allowed && self.filter.as_ref().is_none_or(|filter| filter(url))
+    }
+}
+
+impl std::fmt::Debug for NavigationPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NavigationPolicy")
+            .field("allowed", &self.allowed)
+            .field("filter", &self.filter.is_some())
+            .field("open_external", &self.open_external)
+            .finish()
+    }
+}
+
+/// Open `url` in the system browser. Only `http` and `https` urls are opened, so pages
+/// can't make the OS open local files, executables or custom protocol handlers.
+pub(crate) fn open_external(url: &str) -> bool {
+    if !is_web_url(url) {
+        warn!("Refusing to open '{url}' in the system browser: not an http(s) url");
+        return false;
+    }
+
+    match open::that_detached(url) {
+        Ok(()) => true,
+        Err(e) => {
+            warn!("Failed to open '{url}' in the system browser: {e}");
+            false
+        }
+    }
+}
+
+fn is_web_url(url: &str) -> bool {
+    let Some((scheme, rest)) = url.split_once(':') else {
+        return false;
+    };
+
+    matches!(scheme.to_ascii_lowercase().as_str(), "http" | "https") && rest.starts_with("//")
+}
+
+/// Scheme entries end with `:`, other entries are origins.
+fn matches_entry(url: &str, entry: &str) -> bool {
+    let Some(prefix) = url.get(..entry.len()) else {
+        return false;
+    };
+
+    if !prefix.eq_ignore_ascii_case(entry) {
+        return false;
+    }
+
+    if entry.ends_with(':') {
+        return true;
+    }
+
+    // `https://example.com` must not match `https://example.com.evil` or `https://example.com:81`
+    matches!(
+        url[entry.len()..].chars().next(),
+        None | Some('/' | '?' | '#')
+    )
+}
+
+/// [NavigationPolicy] shared with wry navigation handler.
+/// Required by [crate::components::webview::WebViewComponent] and kept in sync automatically.
+#[derive(Component, Clone, Default, Debug)]
+pub struct SharedNavigationPolicy(Arc<RwLock<NavigationPolicy>>);
+
+impl SharedNavigationPolicy {
+    pub(crate) fn set(&self, policy: NavigationPolicy) {
+        *self.0.write().unwrap() = policy;
+    }
+
+    pub(crate) fn is_allowed(&self, url: &str) -> bool {
+        self.0.read().unwrap().is_allowed(url)
+    }
+
+    pub(crate) fn open_external(&self) -> bool {
+        self.0.read().unwrap().open_external()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_web_urls_open_externally() {
+        assert!(is_web_url("https://example.com"));
+        assert!(is_web_url("HTTP://example.com/path"));
+        assert!(!is_web_url("file:///etc/passwd"));
+        assert!(!is_web_url("C:\\Windows\\System32\\calc.exe"));
+        assert!(!is_web_url("ms-settings:privacy"));
+        assert!(!is_web_url("https:example.com"));
+        assert!(!is_web_url("/usr/bin/xterm"));
+    }
+
+    #[test]
+    fn entries_match_origins_and_schemes() {
+        assert!(matches_entry("https://example.com", "https://example.com"));
+        assert!(matches_entry(
+            "https://example.com/x?y#z",
+            "https://example.com"
+        ));
+        assert!(matches_entry(
+            "HTTPS://EXAMPLE.COM/x",
+            "https://example.com"
+        ));
+        assert!(matches_entry(
+            "https://example.com?q",
+            "https://example.com"
+        ));
+        assert!(!matches_entry(
+            "https://example.com.evil/x",
+            "https://example.com"
+        ));
+        assert!(!matches_entry(
+            "https://example.com:81/x",
+            "https://example.com"
+        ));
+        assert!(!matches_entry(
+            "http://example.com/x",
+            "https://example.com"
+        ));
+        assert!(!matches_entry("https://example", "https://example.com"));
+        assert!(matches_entry("file:///index.html", "file:"));
+        assert!(!matches_entry("files:///index.html", "file:"));
+    }
+
+    #[test]
+    fn trailing_slash_of_allowed_origins_is_ignored() {
+        let policy = NavigationPolicy::allow(["https://example.com/", "file:"]);
+        assert!(policy.is_allowed("https://example.com"));
+        assert!(policy.is_allowed("https://example.com/x"));
+        assert!(policy.is_allowed("file:///index.html"));
+        assert!(policy.is_allowed("about:blank"));
+        assert!(!policy.is_allowed("https://example.com.evil/x"));
+    }
+}
@@ -1,4 +1,8 @@
+use std::sync::Arc;
+
 use crate::{WryPosition, WrySize};
+use bevy::ecs::component::ComponentId;
+use bevy::ecs::world::DeferredWorld;
 use bevy::prelude::*;
 use bevy::utils::hashbrown::hash_map::Values;
 use bevy::utils::hashbrown::HashMap;
@@ -6,27 +10,190 @@ use wry::dpi::{LogicalPosition, LogicalSize};
 use wry::WebView;
 
 use crate::error::Error;
+use crate::events::lifecycle::WebViewDestroyed;
+use crate::events::{InMessageBus, NativeEventBus, OutMessageBus};
 
 use super::bounds::{Position, Size};
+use super::navigation::SharedNavigationPolicy;
 use super::Anchor;
 
-#[derive(Component, Debug)]
+/// Main webview component.
+///
+/// Message busses are required components, so events sent before the native webview is
+/// created are queued until the page is [Ready]. [ReadySignal] defaults to
+/// [ReadySignal::Handshake].
+///
+/// Webviews are identified by their entity. Optional name is a label, that can be used to
+/// find the entity with [WebViewNames]. Names have to be unique.
+///
+/// Removing this component (or despawning the entity) destroys the native webview.
+#[derive(Component, Debug, Default)]
+#[require(
+    InMessageBus,
+    OutMessageBus,
+    NativeEventBus,
+    WebViewLoadState,
+    ReadySignal,
+    SharedNavigationPolicy
+)]
+#[component(on_insert = index_webview_name, on_replace = destroy_webview)]
 pub struct WebViewComponent {
-    pub webview_name: String,
+    pub webview_name: Option<String>,
+}
+
+/// Add name of inserted [WebViewComponent] to [WebViewNames].
+/// Name already used by another entity is not indexed and triggers [WebViewFailed] with
+/// [Error::DuplicateName] on the entity.
+fn index_webview_name(world: DeferredWorld, entity: Entity, _: ComponentId) {
+    index_name(world, entity);
+}
+
+fn index_name(mut world: DeferredWorld, entity: Entity) {
+    let name = world
+        .get::<WebViewComponent>(entity)
+        .and_then(|webview| webview.webview_name.clone());
+    let Some(name) = name else {
+        return;
+    };
+
+    let Some(mut names) = world.get_resource_mut::<WebViewNames>() else {
+        // Resources can't be inserted from hooks, index the name once it's added
+        world.commands().queue(move |world: &mut World| {
+            world.init_resource::<WebViewNames>();
+            index_name(world.into(), entity);
+        });
+        return;
+    };
+
+    match names.get(&name) {
+        Some(existing) if existing != entity => {
+            let error = Error::DuplicateName(name, existing);
+            error!("Failed to name webview {entity}: {error}");
+            world
+                .commands()
+                .trigger_targets(WebViewFailed(Arc::new(error)), entity);
+        }
+        _ => {
+            names.names.insert(name, entity);
+        }
+    }
+}
+
+/// Drop native webview of the entity and trigger [WebViewDestroyed].
+///
+/// Runs on despawn, removal and replacement of [WebViewComponent]. Replaced component
+/// gets a new native webview, because [Initialized] is removed as well.
+fn destroy_webview(mut world: DeferredWorld, entity: Entity, _: ComponentId) {
+    let name = world
+        .get::<WebViewComponent>(entity)
+        .and_then(|webview| webview.webview_name.clone());
+    if let (Some(name), Some(mut names)) = (name, world.get_resource_mut::<WebViewNames>()) {
+        if names.get(&name) == Some(entity) {
+            names.names.remove(&name);
+        }
+    }
+
+    if !world.entity(entity).contains::<Initialized>() {
+        return;
+    }
+
+    let mut commands = world.commands();
+    commands.queue(move |world: &mut World| {
+        if let Some(mut webviews) = world.get_non_send_resource_mut::<WebViews>() {
+            if let Err(e) = webviews.remove_webview(entity) {
+                warn!("Failed to destroy webview: {e}");
+            }
+        }
+    });
+    commands.entity(entity).remove::<(Initialized, Ready)>();
+    commands.trigger_targets(WebViewDestroyed, entity);
 }
 
 impl WebViewComponent {
     pub fn new(name: impl Into<String>) -> Self {
         Self {
-            webview_name: name.into(),
+            webview_name: Some(name.into()),
         }
     }
+
+    pub fn name(&self) -> Option<&str> {
+        self.webview_name.as_deref()
+    }
 }
 
-#[derive(Debug, Component)]
+/// Page loaded by the webview. Changing it navigates existing webview to the new source.
+#[derive(Debug, Component, Clone)]
 pub enum Source {
     Html(String),
     Url(String),
+    /// Path in the default asset source, served with [crate::protocol::asset_url]
+    Asset(String),
+    /// Path in UI bundle registered with [crate::register_ui_bundle],
+    /// served with [crate::protocol::bundle_url]
+    Bundle {
+        bundle: String,
+        path: String,
+    },
+}
+
+/// [Command] navigating webview to a new [Source], without recreating the native webview.
+///
+/// Example
+/// ```rust
+/// use bevy::prelude::*;
+/// use bevy_wry::components::webview::{Navigate, WebViewComponent};
+///
+/// fn open_settings(mut commands: Commands, webview: Single<Entity, With<WebViewComponent>>) {
+///     commands.queue(Navigate::url(*webview, "http://localhost:8080/settings"));
+/// }
+/// ```
+pub struct Navigate {
+    pub webview: Entity,
+    pub source: Source,
+}
+
+impl Navigate {
+    pub fn url(webview: Entity, url: impl Into<String>) -> Self {
+        Self {
+            webview,
+            source: Source::Url(url.into()),
+        }
+    }
+
+    pub fn html(webview: Entity, html: impl Into<String>) -> Self {
+        Self {
+            webview,
+            source: Source::Html(html.into()),
+        }
+    }
+
+    pub fn asset(webview: Entity, path: impl Into<String>) -> Self {
+        Self {
+            webview,
+            source: Source::Asset(path.into()),
+        }
+    }
+
+    pub fn bundle(webview: Entity, bundle: impl Into<String>, path: impl Into<String>) -> Self {
+        Self {
+            webview,
+            source: Source::Bundle {
+                bundle: bundle.into(),
+                path: path.into(),
+            },
+        }
+    }
+}
+
+impl Command for Navigate {
+    fn apply(self, world: &mut World) {
+        match world.get_entity_mut(self.webview) {
+            Ok(mut entity) if entity.contains::<WebViewComponent>() => {
+                entity.insert(self.source);
+            }
+            _ => warn!("Failed to navigate: {} is not a webview", self.webview),
+        }
+    }
 }
 
 #[derive(Debug, Component)]
@@ -35,6 +202,16 @@ pub struct Transparency(pub bool);
 #[derive(Debug, Component)]
 pub struct Fullscreen(pub bool);
 
+/// Signal marking the page as [Ready].
+#[derive(Debug, Component, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReadySignal {
+    /// Page calls `bevy.ready()`, automatically on `DOMContentLoaded` by default
+    #[default]
+    Handshake,
+    /// Page finished loading
+    PageLoad,
+}
+
 #[derive(Bundle)]
 pub struct WebViewBundle {
     pub webview: WebViewComponent,
@@ -43,11 +220,13 @@ pub struct WebViewBundle {
     pub size: Size,
     pub source: Source,
     pub transparency: Transparency,
+    pub ready_signal: ReadySignal,
 }
 
+/// Builder of [WebViewBundle]. Use [WebViewBundleBuilder::default] for unnamed webview.
 #[derive(Debug, Default)]
 pub struct WebViewBundleBuilder {
-    /// Main webview component
+    /// Main webview component. Default: unnamed
     webview: Option<WebViewComponent>,
     /// Webview size. Default: fullscreen
     size: Option<Size>,
@@ -55,10 +234,12 @@ pub struct WebViewBundleBuilder {
     position: Option<Position>,
     /// Webview anchor. Default [Anchor::FullScreen]
     anchor: Option<Anchor>,
-    /// Webview source. Either Html or Url. Default: Source::Html("")
+    /// Webview source. Default: Source::Html("")
     source: Option<Source>,
     /// Transparent webview. Default: false
     transparent: Option<bool>,
+    /// Signal marking the page as ready. Default: [ReadySignal::Handshake]
+    ready_signal: Option<ReadySignal>,
 }
 
 impl WebViewBundleBuilder {
@@ -93,6 +274,21 @@ impl WebViewBundleBuilder {
         self
     }
 
+    /// Set webview source to a file in the default asset source, e.g. `ui/index.html`
+    pub fn with_asset(mut self, path: impl Into<String>) -> Self {
+        self.source = Some(Source::Asset(path.into()));
+        self
+    }
+
+    /// Set webview source to a file of UI bundle registered as `bundle`, e.g. `index.html`
+    pub fn with_bundle(mut self, bundle: impl Into<String>, path: impl Into<String>) -> Self {
+        self.source = Some(Source::Bundle {
+            bundle: bundle.into(),
+            path: path.into(),
+        });
+        self
+    }
+
     /// Set webview source to Html
     pub fn with_html(mut self, html: String) -> Self {
         self.source = Some(Source::Html(html));
@@ -105,6 +301,12 @@ impl WebViewBundleBuilder {
         self
     }
 
+    /// Set signal marking the page as ready. Default: [ReadySignal::Handshake]
+    pub fn with_ready_signal(mut self, ready_signal: ReadySignal) -> Self {
+        self.ready_signal = Some(ready_signal);
+        self
+    }
+
     pub fn build(self) -> WebViewBundle {
         let position = self
             .position
@@ -113,14 +315,16 @@ impl WebViewBundleBuilder {
         let source = self.source.unwrap_or(Source::Html("".to_string()));
         let transparency = Transparency(self.transparent.unwrap_or(false));
         let anchor = self.anchor.unwrap_or(Anchor::FullScreen);
+        let ready_signal = self.ready_signal.unwrap_or_default();
 
         WebViewBundle {
-            webview: self.webview.expect("WebView builder sets name"),
+            webview: self.webview.unwrap_or_default(),
             position,
             size,
             source,
             transparency,
             anchor,
+            ready_signal,
         }
     }
 }
@@ -128,30 +332,77 @@ impl WebViewBundleBuilder {
 #[derive(Component)]
 pub struct Initialized;
 
+/// Inserted and triggered on the webview entity when its native webview can't be created.
+/// Remove this component to retry.
+///
+/// Also triggered, without being inserted, when the webview name is already used by another
+/// entity. The webview is still created, but can't be found by name in [WebViewNames].
+#[derive(Component, Event, Debug, Clone)]
+pub struct WebViewFailed(pub Arc<Error>);
+
+/// Loading state of the page, updated before [crate::events::lifecycle::PageLoadStarted]
+/// and [crate::events::lifecycle::PageLoadFinished] are triggered.
+#[derive(Debug, Component, Clone, Default, PartialEq, Eq)]
+pub enum WebViewLoadState {
+    /// Native webview is not created yet or didn't start loading
+    #[default]
+    NotStarted,
+    Loading {
+        url: String,
+    },
+    Loaded {
+        url: String,
+    },
+}
+
+/// Inserted when the page is ready to receive events and removed when it starts loading
+/// another page. Scripts are evaluated only in ready webviews.
+#[derive(Component)]
+pub struct Ready;
+
+/// Native webviews, by entity of their [WebViewComponent].
 #[derive(Default)]
 pub struct WebViews {
-    /// TODO: Use HashMap<Entity, WebView> instead
-    webviews: HashMap<String, WebView>,
+    webviews: HashMap<Entity, WebView>,
 }
 
 impl WebViews {
-    pub fn insert(&mut self, name: String, webview: WebView) {
-        self.webviews.insert(name.clone(), webview);
+    pub fn insert(&mut self, entity: Entity, webview: WebView) {
+        self.webviews.insert(entity, webview);
     }
 
-    pub fn get_webview(&self, name: &String) -> Option<&WebView> {
-        self.webviews.get(name)
+    pub fn get_webview(&self, entity: Entity) -> Option<&WebView> {
+        self.webviews.get(&entity)
     }
 
-    pub fn get_all(&self) -> Values<'_, String, WebView> {
+    pub fn get_all(&self) -> Values<'_, Entity, WebView> {
         self.webviews.values()
     }
 
-    pub fn remove_webview(&mut self, name: &String) -> Result<(), Error> {
+    pub fn remove_webview(&mut self, entity: Entity) -> Result<(), Error> {
         self.webviews
-            .remove(name)
-            .ok_or_else(|| Error::FailedToGetWebview(name.clone()))?;
+            .remove(&entity)
+            .ok_or(Error::FailedToGetWebview(entity))?;
 
         Ok(())
     }
 }
+
+/// [Resource] indexing webview entities by [WebViewComponent] name.
+#[derive(Resource, Default, Debug)]
+pub struct WebViewNames {
+    names: HashMap<String, Entity>,
+}
+
+impl WebViewNames {
+    /// Entity of the webview named `name`.
+    pub fn get(&self, name: &str) -> Option<Entity> {
+        self.names.get(name).copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Entity)> {
+        self.names
+            .iter()
+            .map(|(name, entity)| (name.as_str(), *entity))
+    }
+}
@@ -1,3 +1,4 @@
+use bevy::prelude::Entity;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,8 +7,10 @@ pub enum Error {
     FailedToGetMainWindow,
     #[error("wry error: {0}")]
     Wry(#[from] wry::Error),
-    #[error("webview with name '{0}' doesn't exist")]
-    FailedToGetWebview(String),
+    #[error("webview of entity {0} doesn't exist")]
+    FailedToGetWebview(Entity),
+    #[error("webview name '{0}' is already used by entity {1}")]
+    DuplicateName(String, Entity),
     #[error("cannot update anchnor of a webview with not relative bounds")]
     FailedToUpdateAnchor,
 }
@@ -1,7 +1,29 @@
-#[derive(Debug)]
+use bevy::prelude::*;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Error {
+    #[error("failed to deserialize message")]
     Deserialize,
+    #[error("message type is not registered")]
     BadMessageType,
+    #[error("close requested")]
     CloseRequested,
+    #[error("failed to evaluate script")]
     EvaluateScript,
+    #[error("webview is not initialized")]
+    WebViewNotInitialized,
+}
+
+/// Error reported when a message exchanged with [wry::WebView] could not be handled.
+///
+/// Triggered on the webview entity instead of panicking, so it can be observed with
+/// `Trigger<WryIpcError>`. Every error is also logged as a warning.
+#[derive(Event, Debug, Clone)]
+pub struct WryIpcError {
+    /// Entity of the webview that sent or should receive the message
+    pub webview: Entity,
+    /// Raw message: JSON received via IPC or script meant to be evaluated
+    pub message: String,
+    pub error: Error,
 }
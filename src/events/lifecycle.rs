@@ -0,0 +1,161 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::components::navigation::open_external;
+use crate::components::webview::WebViewBundleBuilder;
+
+/// Envelope tag of the ready handshake sent by `bevy.ready()`.
+pub const READY_TAG: &str = "__bevy_ready";
+
+/// Envelope tag of errors reported by `bevy.reportError()`.
+pub const JS_ERROR_TAG: &str = "__bevy_error";
+
+/// Prefix of envelope tags reserved for bevy_wry. Pages can only send [READY_TAG],
+/// [JS_ERROR_TAG] and [crate::events::query::JS_QUERY_TAG], other reserved tags are rejected.
+pub const RESERVED_TAG_PREFIX: &str = "__bevy_";
+
+/// Triggered on the webview entity when the page becomes ready to receive events,
+/// see [crate::components::webview::ReadySignal].
+///
+/// Events sent to the webview before it is ready are queued and evaluated right after
+/// this trigger, so observers can send initial state to the page.
+#[derive(Event, Debug, Clone)]
+pub struct WebViewReady {
+    /// Version of the client used by the page, see [crate::client::CLIENT_VERSION].
+    /// `None` when readiness was signalled by page load completion.
+    pub version: Option<String>,
+}
+
+/// Triggered on the webview entity when its native webview is destroyed, either because
+/// the entity was despawned or [crate::components::webview::WebViewComponent] was removed.
+/// The entity may no longer exist when observers run.
+#[derive(Event, Debug, Clone)]
+pub struct WebViewDestroyed;
+
+/// Triggered on the webview entity when it starts loading a page.
+/// [crate::components::webview::WebViewLoadState] is already updated.
+#[derive(Event, Debug, Clone)]
+pub struct PageLoadStarted {
+    pub url: String,
+}
+
+/// Triggered on the webview entity when it finishes loading a page.
+/// [crate::components::webview::WebViewLoadState] is already updated.
+#[derive(Event, Debug, Clone)]
+pub struct PageLoadFinished {
+    pub url: String,
+}
+
+/// Triggered on the webview entity when the page navigates to `url`, e.g. after link click.
+/// This event is only a notification - use [crate::components::navigation::NavigationPolicy]
+/// to block navigation.
+#[derive(Event, Debug, Clone)]
+pub struct NavigationRequested {
+    pub url: String,
+}
+
+/// Triggered on the webview entity when [crate::components::navigation::NavigationPolicy]
+/// blocks navigation to `url`.
+#[derive(Event, Debug, Clone)]
+pub struct NavigationBlocked {
+    pub url: String,
+    /// `url` was opened in the system browser instead. Only `http` and `https` urls are
+    /// opened externally
+    pub opened_externally: bool,
+}
+
+/// Triggered on the webview entity when the page asks for a new window, with `window.open`
+/// or a `target="_blank"` link.
+///
+/// Native popups are never opened. The request is denied unless an observer opens `url`
+/// externally or spawns a new webview entity.
+///
+/// `url` comes from the page, so check it before acting on it.
+///
+/// Example
+/// ```rust
+/// use bevy::prelude::*;
+/// use bevy_wry::components::Anchor;
+/// use bevy_wry::events::lifecycle::NewWindowRequested;
+/// use bevy_wry::wry::dpi::LogicalSize;
+///
+/// fn on_new_window(trigger: Trigger<NewWindowRequested>, mut commands: Commands) {
+///     let request = trigger.event();
+///     if request.url.starts_with("http://localhost:8080/") {
+///         let popup = request
+///             .webview()
+///             .with_anchor(Anchor::Center)
+///             .with_size(LogicalSize::new(800.0, 600.0));
+///         commands.spawn(popup.build());
+///     } else if request.url.starts_with("https://docs.example.com/") {
+///         request.open_external();
+///     }
+/// }
+/// ```
+#[derive(Event, Debug, Clone)]
+pub struct NewWindowRequested {
+    pub url: String,
+}
+
+impl NewWindowRequested {
+    /// Open `url` in the system browser. Returns `false` if it failed or `url` is not
+    /// an `http` or `https` url.
+    pub fn open_external(&self) -> bool {
+        open_external(&self.url)
+    }
+
+    /// Builder of unnamed webview loading `url`, to be spawned as overlay or popup.
+    pub fn webview(&self) -> WebViewBundleBuilder {
+        WebViewBundleBuilder::default().with_url(self.url.clone())
+    }
+}
+
+/// Triggered on the webview entity when the page reports an error, e.g. uncaught exception.
+#[derive(Event, Debug, Clone, Deserialize)]
+pub struct WebViewJsError {
+    pub message: String,
+    /// Script the error originated from
+    pub source: Option<String>,
+    pub line: Option<u32>,
+}
+
+/// Handshake sent by `bevy.ready()`.
+#[derive(Event, Deserialize)]
+pub(crate) struct ReadyHandshake {
+    pub version: String,
+}
+
+/// Page load notification from wry page load handler.
+#[derive(Event)]
+pub(crate) struct PageLoad {
+    pub finished: bool,
+    pub url: String,
+}
+
+impl PageLoad {
+    pub(crate) fn new(event: wry::PageLoadEvent, url: String) -> Self {
+        Self {
+            finished: matches!(event, wry::PageLoadEvent::Finished),
+            url,
+        }
+    }
+}
+
+/// Notification of native wry handler, pushed to [crate::events::NativeEventBus].
+pub(crate) enum NativeEvent {
+    /// Message posted by the page with `window.ipc.postMessage`
+    Ipc(String),
+    PageLoad(PageLoad),
+    Navigation(NavigationRequested),
+    NavigationBlocked(NavigationBlocked),
+    NewWindow(NewWindowRequested),
+}
+
+/// Returns `true` if pages can send messages with `tag`.
+pub(crate) fn is_page_tag(tag: &str) -> bool {
+    !tag.starts_with(RESERVED_TAG_PREFIX)
+        || matches!(
+            tag,
+            READY_TAG | JS_ERROR_TAG | crate::events::query::JS_QUERY_TAG
+        )
+}
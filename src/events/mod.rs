@@ -1,17 +1,181 @@
 pub mod error;
+pub mod lifecycle;
+pub mod query;
+pub mod request;
 
-use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
 use bevy::prelude::*;
+use bevy::utils::HashMap;
+pub use bevy_wry_derive::OutWryEvent;
 use serde::{Deserialize, Serialize};
 
-pub trait OutWryEvent: Event + Serialize + Send {
-    fn to_script(&self) -> String;
+use lifecycle::NativeEvent;
+
+/// Event sent from Bevy to [wry::WebView].
+///
+/// By default the event is serialized to JSON and dispatched to the page, where it can be
+/// received in two ways:
+/// - `bevy.on(tag, (payload) => ...)` - registers a callback, returns a function removing it
+/// - `window.addEventListener("bevy:" + tag, (e) => ...)` - DOM `CustomEvent` with payload in `e.detail`
+///
+/// Override [OutWryEvent::to_script] to evaluate a custom script instead, or derive it
+/// to call JS functions with JSON encoded arguments:
+/// ```rust
+/// use bevy::prelude::*;
+/// use bevy_wry::events::OutWryEvent;
+///
+/// #[derive(Event, serde::Serialize, OutWryEvent)]
+/// enum OutCommand {
+///     // `showButton();`
+///     #[wry(js = "showButton")]
+///     ShowButton,
+///     // `ui.setHealth(current, max);`
+///     #[wry(js = "ui.setHealth")]
+///     SetHealth { current: f32, max: f32 },
+///     // `bevy.on("OutCommand", ...)` listeners, like without the derive
+///     Inventory(Vec<String>),
+/// }
+///
+/// assert_eq!(OutCommand::ShowButton.to_script(), "showButton();");
+/// ```
+///
+/// Function names are pasted into the script, so anything but a (dotted) JS identifier
+/// doesn't compile:
+/// ```compile_fail
+/// use bevy::prelude::*;
+/// use bevy_wry::events::OutWryEvent;
+///
+/// #[derive(Event, serde::Serialize, OutWryEvent)]
+/// #[wry(js = "alert(document.cookie); showButton")]
+/// struct ShowButton;
+/// ```
+///
+/// Neither do unknown or malformed `#[wry(...)]` attributes:
+/// ```compile_fail
+/// use bevy::prelude::*;
+/// use bevy_wry::events::OutWryEvent;
+///
+/// #[derive(Event, serde::Serialize, OutWryEvent)]
+/// #[wry(script = "showButton")]
+/// struct ShowButton;
+/// ```
+/// ```compile_fail
+/// use bevy::prelude::*;
+/// use bevy_wry::events::OutWryEvent;
+///
+/// #[derive(Event, serde::Serialize, OutWryEvent)]
+/// struct SetHealth {
+///     #[wry(js)]
+///     current: f32,
+/// }
+/// ```
+pub trait OutWryEvent: Event + Serialize + Send + Sized {
+    /// Tag the event is dispatched under. Default: [default_tag]
+    fn tag() -> &'static str {
+        default_tag::<Self>()
+    }
+
+    /// Script evaluated in the page when this event is sent. Default: [dispatch_script]
+    fn to_script(&self) -> String {
+        dispatch_script(Self::tag(), self)
+    }
+}
+
+/// JSON encoded `value`, safe to paste into a script as a JS expression.
+pub fn js_arg(value: &impl Serialize) -> String {
+    serde_json::to_string(value).unwrap_or_else(|e| {
+        warn!("Failed to serialize JS argument: {e}");
+        "null".to_string()
+    })
+}
+
+/// Script calling JS `function` with already encoded `args`, see [js_arg].
+pub fn js_call(function: &str, args: &[String]) -> String {
+    format!("{function}({});", args.join(", "))
+}
+
+/// Script dispatching serialized `payload` to `bevy.on(tag, ...)` callbacks and
+/// as `bevy:<tag>` DOM `CustomEvent`.
+pub fn dispatch_script(tag: &str, payload: &impl Serialize) -> String {
+    let tag = serde_json::Value::from(tag);
+    match serde_json::to_string(payload) {
+        Ok(payload) => format!("window.bevy.__dispatch({tag}, {payload});"),
+        Err(e) => {
+            warn!("Failed to serialize {tag} event: {e}");
+            let message = serde_json::Value::from(format!("failed to serialize {tag} event: {e}"));
+            format!("console.error({message});")
+        }
+    }
 }
 
 pub trait InWryEvent<'de>: Event + Deserialize<'de> + Send {}
 impl<'de, T> InWryEvent<'de> for T where T: Event + Deserialize<'de> + Send {}
 
+/// Envelope wrapping every message received from [wry::WebView].
+///
+/// `tag` selects the [InWryEvent] registered under the same tag and `payload` is
+/// deserialized into it, e.g. `{"type": "InCommand", "payload": {"Rotate": {"angle": 30}}}`.
+/// Missing `payload` is treated as `null`, which is enough for unit structs.
+/// Messages sent with `bevy.invoke` carry an `id` used to settle the returned `Promise`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct IpcEnvelope {
+    #[serde(rename = "type")]
+    pub tag: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+    #[serde(default)]
+    pub payload: serde_json::Value,
+}
+
+/// Deserializes `payload` and triggers the resulting event on the webview entity.
+pub(crate) type IncomingEventTrigger = fn(
+    &mut Commands,
+    Entity,
+    &OutMessageBus,
+    Option<u64>,
+    serde_json::Value,
+) -> Result<(), serde_json::Error>;
+
+/// [Resource] mapping envelope tags to registered [InWryEvent] and
+/// [request::InWryRequest] types.
+#[derive(Resource, Default)]
+pub struct IncomingEvents {
+    triggers: HashMap<String, IncomingEventTrigger>,
+}
+
+impl IncomingEvents {
+    pub(crate) fn insert(&mut self, tag: String, trigger: IncomingEventTrigger) {
+        if self.triggers.insert(tag.clone(), trigger).is_some() {
+            panic!("incoming event tag '{tag}' is already registered");
+        }
+    }
+
+    pub(crate) fn get(&self, tag: &str) -> Option<&IncomingEventTrigger> {
+        self.triggers.get(tag)
+    }
+
+    /// Returns `true` if an event type was registered under `tag`.
+    pub fn contains(&self, tag: &str) -> bool {
+        self.triggers.contains_key(tag)
+    }
+
+    /// Iterate over all registered tags.
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.triggers.keys().map(String::as_str)
+    }
+}
+
+/// Default envelope tag of an event type: its name without module path, e.g. `InCommand`.
+pub fn default_tag<E>() -> &'static str {
+    let full_name = std::any::type_name::<E>();
+    let without_generics = full_name.split('<').next().unwrap_or(full_name);
+    without_generics
+        .rsplit("::")
+        .next()
+        .unwrap_or(without_generics)
+}
+
 #[derive(Deserialize, Serialize, Event)]
 pub struct EmptyOutEvent;
 
@@ -54,8 +218,28 @@ impl Clone for MessageBus {
     }
 }
 
+/// Incoming messages queued from Rust, handled like messages posted by the page.
+/// Messages posted by the page arrive through [NativeEventBus].
 #[derive(Component, Deref, Default)]
 pub struct InMessageBus(MessageBus);
 
 #[derive(Component, Deref, Default)]
 pub struct OutMessageBus(MessageBus);
+
+/// Notifications of native wry handlers (ipc messages, page load, navigation, new window
+/// requests), in the order they were received.
+///
+/// One queue keeps e.g. `bevy.ready()` handshake after the page load that preceded it.
+/// Pages can only add ipc messages, so they can't forge other notifications.
+#[derive(Component, Clone, Default)]
+pub struct NativeEventBus(Arc<Mutex<Vec<NativeEvent>>>);
+
+impl NativeEventBus {
+    pub(crate) fn push(&self, event: NativeEvent) {
+        self.0.lock().unwrap().push(event);
+    }
+
+    pub(crate) fn drain(&self) -> Vec<NativeEvent> {
+        self.0.lock().unwrap().drain(..).collect()
+    }
+}
@@ -0,0 +1,217 @@
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use thiserror::Error;
+
+use super::OutMessageBus;
+
+/// Envelope tag used by the webview to answer [JsQuery].
+pub const JS_QUERY_TAG: &str = "__bevy_query";
+
+/// Default time [JsQuery] waits for the page to answer.
+pub const DEFAULT_JS_QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+static NEXT_QUERY_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum JsQueryError {
+    #[error("page didn't answer in time")]
+    Timeout,
+    #[error("script threw: {0}")]
+    Script(String),
+    #[error("failed to serialize arguments: {0}")]
+    Serialize(String),
+    #[error("failed to deserialize result: {0}")]
+    Deserialize(String),
+    #[error("webview is not initialized")]
+    WebViewNotInitialized,
+}
+
+/// [Command] asking [wry::WebView] for a value of type `T`.
+///
+/// The answer is triggered as [JsQueryResult] on the webview entity, once the page answers
+/// or the timeout elapses. Queued scripts are evaluated when webview busses are flushed.
+///
+/// Example
+/// ```rust
+/// use bevy::prelude::*;
+/// use bevy_wry::components::webview::WebViewComponent;
+/// use bevy_wry::events::query::{JsQuery, JsQueryResult};
+///
+/// #[derive(serde::Deserialize)]
+/// struct Scroll {
+///     x: f32,
+///     y: f32,
+/// }
+///
+/// fn ask_for_scroll(mut commands: Commands, webview: Single<Entity, With<WebViewComponent>>) {
+///     commands.queue(JsQuery::<Scroll>::eval(
+///         *webview,
+///         "({ x: window.scrollX, y: window.scrollY })",
+///     ));
+/// }
+///
+/// fn on_scroll(trigger: Trigger<JsQueryResult<Scroll>>) {
+///     if let Ok(scroll) = &trigger.event().result {
+///         info!("Page scrolled to {}x{}", scroll.x, scroll.y);
+///     }
+/// }
+/// ```
+pub struct JsQuery<T> {
+    id: u64,
+    webview: Entity,
+    script: String,
+    timeout: Duration,
+    /// Set if the query failed before reaching the page
+    error: Option<JsQueryError>,
+    _result: PhantomData<fn() -> T>,
+}
+
+impl<T> JsQuery<T> {
+    /// Evaluate JS `expression` in the page. If it evaluates to a `Promise`,
+    /// the query is answered with its resolved value.
+    /// Use an IIFE for multiple statements: `(() => { ...; return value; })()`.
+    pub fn eval(webview: Entity, expression: impl AsRef<str>) -> Self {
+        let id = NEXT_QUERY_ID.fetch_add(1, Ordering::Relaxed);
+        let script = format!(
+            "window.bevy.__query({id}, () => ({}));",
+            expression.as_ref()
+        );
+
+        Self {
+            id,
+            webview,
+            script,
+            timeout: DEFAULT_JS_QUERY_TIMEOUT,
+            error: None,
+            _result: PhantomData,
+        }
+    }
+
+    /// Call JS handler registered with `bevy.handle(name, handler)`, passing `args` as its argument.
+    /// If `args` can't be serialized, the query is answered with [JsQueryError::Serialize].
+    pub fn call(webview: Entity, name: &str, args: impl serde::Serialize) -> Self {
+        let name = serde_json::Value::from(name);
+        match serde_json::to_value(args) {
+            Ok(args) => Self::eval(webview, format!("window.bevy.__call({name}, {args})")),
+            Err(e) => {
+                let mut query = Self::eval(webview, "undefined");
+                query.error = Some(JsQueryError::Serialize(e.to_string()));
+                query
+            }
+        }
+    }
+
+    /// Set how long to wait for the answer. Default: [DEFAULT_JS_QUERY_TIMEOUT]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Id of this query, matching [JsQueryResult::id].
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+}
+
+impl<T: DeserializeOwned + Send + Sync + 'static> Command for JsQuery<T> {
+    fn apply(self, world: &mut World) {
+        if let Some(error) = self.error {
+            world.trigger_targets(
+                JsQueryResult::<T> {
+                    id: self.id,
+                    result: Err(error),
+                },
+                self.webview,
+            );
+            return;
+        }
+
+        let Some(out_bus) = world.get::<OutMessageBus>(self.webview) else {
+            world.trigger_targets(
+                JsQueryResult::<T> {
+                    id: self.id,
+                    result: Err(JsQueryError::WebViewNotInitialized),
+                },
+                self.webview,
+            );
+            return;
+        };
+
+        out_bus.write().push(self.script);
+        let deadline = world.resource::<Time<Real>>().elapsed() + self.timeout;
+        world.resource_mut::<JsQueries>().pending.insert(
+            self.id,
+            PendingJsQuery {
+                webview: self.webview,
+                deadline,
+                resolve: resolve_js_query::<T>,
+            },
+        );
+    }
+}
+
+/// Answer to [JsQuery], triggered on the webview entity.
+#[derive(Event, Debug)]
+pub struct JsQueryResult<T: Send + Sync + 'static> {
+    /// Id of the answered [JsQuery]
+    pub id: u64,
+    pub result: Result<T, JsQueryError>,
+}
+
+/// Answer sent by the page with [JS_QUERY_TAG].
+#[derive(Event, Deserialize)]
+pub(crate) struct JsQueryResponse {
+    pub id: u64,
+    pub result: JsQueryOutcome,
+}
+
+#[derive(Deserialize)]
+pub(crate) enum JsQueryOutcome {
+    #[serde(rename = "ok")]
+    Ok(serde_json::Value),
+    #[serde(rename = "err")]
+    Err(String),
+}
+
+type ResolveJsQuery = fn(&mut Commands, Entity, u64, Result<serde_json::Value, JsQueryError>);
+
+pub(crate) struct PendingJsQuery {
+    pub webview: Entity,
+    pub deadline: Duration,
+    pub resolve: ResolveJsQuery,
+}
+
+/// [Resource] tracking [JsQuery]s waiting for an answer.
+#[derive(Resource, Default)]
+pub struct JsQueries {
+    pub(crate) pending: HashMap<u64, PendingJsQuery>,
+}
+
+impl JsQueries {
+    /// Number of queries waiting for an answer.
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+fn resolve_js_query<T: DeserializeOwned + Send + Sync + 'static>(
+    commands: &mut Commands,
+    webview: Entity,
+    id: u64,
+    result: Result<serde_json::Value, JsQueryError>,
+) {
+    let result = result.and_then(|value| {
+        serde_json::from_value::<T>(value).map_err(|e| JsQueryError::Deserialize(e.to_string()))
+    });
+    commands.trigger_targets(JsQueryResult { id, result }, webview);
+}
@@ -0,0 +1,127 @@
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use super::MessageBus;
+
+/// Request sent from [wry::WebView] with `bevy.invoke(type, payload)`, expecting a response.
+///
+/// Registered requests are triggered as [WryRequest] on the webview entity and the
+/// `Promise` returned by `bevy.invoke` settles when the observer replies via [Responder].
+pub trait InWryRequest: DeserializeOwned + Send + Sync + 'static {
+    type Response: Serialize + Send + Sync + 'static;
+}
+
+/// Event triggered for every incoming [InWryRequest].
+#[derive(Event)]
+pub struct WryRequest<R: InWryRequest> {
+    pub request: R,
+    pub responder: Responder<R::Response>,
+}
+
+impl<R: InWryRequest> WryRequest<R> {
+    /// Resolve the `Promise` returned by `bevy.invoke` with `response`.
+    pub fn respond(&self, response: R::Response) {
+        self.responder.respond(response);
+    }
+
+    /// Reject the `Promise` returned by `bevy.invoke` with `message`.
+    pub fn reject(&self, message: impl Into<String>) {
+        self.responder.reject(message);
+    }
+}
+
+/// Handle used to settle the `Promise` of a single `bevy.invoke` call.
+///
+/// Responder can be cloned and kept to respond in a later frame. Only the first
+/// response is delivered. When all clones are dropped without responding,
+/// the `Promise` is rejected.
+pub struct Responder<T> {
+    inner: Arc<ResponderInner>,
+    _response: PhantomData<fn(T)>,
+}
+
+impl<T> Clone for Responder<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            _response: PhantomData,
+        }
+    }
+}
+
+impl<T: Serialize> Responder<T> {
+    pub(crate) fn new(id: u64, out_bus: MessageBus) -> Self {
+        Self {
+            inner: Arc::new(ResponderInner {
+                id,
+                out_bus,
+                settled: AtomicBool::new(false),
+            }),
+            _response: PhantomData,
+        }
+    }
+
+    /// Id of the `bevy.invoke` call, unique per webview.
+    pub fn id(&self) -> u64 {
+        self.inner.id
+    }
+
+    /// Resolve the `Promise` with serialized `response`.
+    pub fn respond(&self, response: T) {
+        match serde_json::to_value(response) {
+            Ok(value) => self.inner.settle(Settle::Ok(value)),
+            Err(e) => self.inner.settle(Settle::Err(e.to_string())),
+        }
+    }
+
+    /// Reject the `Promise` with an `Error` carrying `message`.
+    pub fn reject(&self, message: impl Into<String>) {
+        self.inner.settle(Settle::Err(message.into()));
+    }
+
+    /// Returns `true` if the `Promise` was already resolved or rejected.
+    pub fn is_settled(&self) -> bool {
+        self.inner.settled.load(Ordering::Acquire)
+    }
+}
+
+enum Settle {
+    Ok(serde_json::Value),
+    Err(String),
+}
+
+struct ResponderInner {
+    id: u64,
+    out_bus: MessageBus,
+    settled: AtomicBool,
+}
+
+impl ResponderInner {
+    fn settle(&self, result: Settle) {
+        if self.settled.swap(true, Ordering::AcqRel) {
+            warn!("Request {} was already settled", self.id);
+            return;
+        }
+
+        let result = match result {
+            Settle::Ok(value) => serde_json::json!({ "ok": value }),
+            Settle::Err(message) => serde_json::json!({ "err": message }),
+        };
+        self.out_bus
+            .write()
+            .push(format!("window.bevy.__settle({}, {result});", self.id));
+    }
+}
+
+impl Drop for ResponderInner {
+    fn drop(&mut self) {
+        if !self.settled.load(Ordering::Acquire) {
+            self.settle(Settle::Err("request was not handled".to_string()));
+        }
+    }
+}
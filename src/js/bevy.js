@@ -0,0 +1,145 @@
+// bevy_wry client, injected into every webview before the page scripts run.
+// This file is a function expression - bevy_wry calls it with the crate version.
+// The API is documented in `src/client.rs`.
+(function (version) {
+  if (window.bevy !== undefined && window.bevy.version !== undefined) {
+    return;
+  }
+
+  const bevy = window.bevy || (window.bevy = {});
+  const pending = new Map();
+  const handlers = new Map();
+  const listeners = new Map();
+  // Request ids start at a random per-page offset, so answers to requests of the previous
+  // page can't settle requests of this one. Ids stay below 2^53 to remain exact numbers.
+  const nonce = new Uint32Array(1);
+  window.crypto.getRandomValues(nonce);
+  let nextId = nonce[0] * 2 ** 20;
+  let ready = false;
+
+  function post(type, payload) {
+    window.ipc.postMessage(JSON.stringify({ type, payload }));
+  }
+
+  bevy.version = version;
+
+  // Send event registered with `register_incoming_event` to Bevy.
+  bevy.send = function (type, payload) {
+    post(type, payload);
+  };
+
+  // Send request to Bevy. Returned Promise settles when the observer of
+  // `WryRequest` responds.
+  bevy.invoke = function (type, payload) {
+    const id = nextId++;
+    return new Promise((resolve, reject) => {
+      pending.set(id, { resolve, reject });
+      window.ipc.postMessage(JSON.stringify({ type, id, payload }));
+    });
+  };
+
+  // Called by Bevy with `{ ok: value }` or `{ err: message }`. Answers to unknown ids
+  // (e.g. sent by the previous page) are ignored.
+  bevy.__settle = function (id, result) {
+    const request = pending.get(id);
+    if (request === undefined) {
+      return;
+    }
+
+    pending.delete(id);
+    if ("err" in result) {
+      request.reject(new Error(result.err));
+    } else {
+      request.resolve(result.ok);
+    }
+  };
+
+  // Listen for `OutWryEvent` dispatched under `type`. Returns function removing the listener.
+  bevy.on = function (type, listener) {
+    if (!listeners.has(type)) {
+      listeners.set(type, new Set());
+    }
+
+    listeners.get(type).add(listener);
+    return () => bevy.off(type, listener);
+  };
+
+  bevy.off = function (type, listener) {
+    const typeListeners = listeners.get(type);
+    if (typeListeners !== undefined) {
+      typeListeners.delete(listener);
+    }
+  };
+
+  // Called by Bevy for every `OutWryEvent` using default `to_script`.
+  bevy.__dispatch = function (type, payload) {
+    for (const listener of listeners.get(type) || []) {
+      try {
+        listener(payload);
+      } catch (error) {
+        bevy.reportError(error);
+      }
+    }
+
+    window.dispatchEvent(new CustomEvent(`bevy:${type}`, { detail: payload }));
+  };
+
+  // Register handler that Bevy can call with `JsQuery::call(webview, name, args)`.
+  bevy.handle = function (name, handler) {
+    handlers.set(name, handler);
+  };
+
+  bevy.__call = function (name, args) {
+    const handler = handlers.get(name);
+    if (handler === undefined) {
+      throw new Error(`no handler registered for '${name}'`);
+    }
+
+    return handler(args);
+  };
+
+  // Called by Bevy to answer `JsQuery`. Promises are awaited.
+  bevy.__query = function (id, query) {
+    Promise.resolve()
+      .then(query)
+      .then(
+        (value) => post("__bevy_query", { id, result: { ok: value === undefined ? null : value } }),
+        (error) => post("__bevy_query", { id, result: { err: String(error) } }),
+      );
+  };
+
+  // Tell Bevy the page is ready to receive events. Called automatically on
+  // `DOMContentLoaded`, unless the page has `<meta name="bevy-wry-manual-ready">`.
+  bevy.ready = function () {
+    if (ready) {
+      return;
+    }
+
+    ready = true;
+    post("__bevy_ready", { version });
+  };
+
+  // Report error to Bevy, where it is triggered as `WebViewJsError`.
+  bevy.reportError = function (error, source, line) {
+    console.error(error);
+    post("__bevy_error", {
+      message: error instanceof Error ? `${error.name}: ${error.message}` : String(error),
+      source: source === undefined ? null : String(source),
+      line: typeof line === "number" ? line : null,
+    });
+  };
+
+  window.addEventListener("error", (event) => {
+    bevy.reportError(event.error || event.message, event.filename, event.lineno);
+  });
+
+  window.addEventListener("unhandledrejection", (event) => {
+    bevy.reportError(event.reason);
+  });
+
+  window.addEventListener("DOMContentLoaded", () => {
+    if (document.querySelector('meta[name="bevy-wry-manual-ready"]') === null) {
+      bevy.ready();
+    }
+  });
+})
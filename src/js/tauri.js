@@ -0,0 +1,100 @@
+// Tauri compatibility shim, injected after the bevy_wry client when enabled with
+// `BevyWryPlugin::with_tauri_shim`. Maps Tauri APIs onto `window.bevy`.
+// The mapping is documented in `src/client.rs`.
+(function () {
+  if (window.__TAURI__ !== undefined || window.bevy === undefined) {
+    return;
+  }
+
+  const bevy = window.bevy;
+  const callbacks = new Map();
+  const unlisteners = new Map();
+  let nextId = 0;
+
+  // Tauri rejects with the error returned by the command, not with `Error`.
+  function invoke(cmd, args) {
+    if (cmd === "plugin:event|listen") {
+      const handler = callbacks.get(args.handler);
+      return Promise.resolve(listen(args.event, (e) => handler && handler(e)));
+    }
+
+    if (cmd === "plugin:event|unlisten") {
+      unlisten(args.eventId);
+      return Promise.resolve(null);
+    }
+
+    if (cmd === "plugin:event|emit" || cmd === "plugin:event|emit_to") {
+      return emit(args.event, args.payload);
+    }
+
+    const payload = args === undefined ? null : args;
+    return bevy.invoke(cmd, payload).catch((error) => {
+      throw error instanceof Error ? error.message : error;
+    });
+  }
+
+  function listen(event, handler) {
+    const id = nextId++;
+    const off = bevy.on(event, (payload) => handler({ event, id, payload }));
+    unlisteners.set(id, off);
+    return id;
+  }
+
+  function unlisten(id) {
+    const off = unlisteners.get(id);
+    if (off !== undefined) {
+      unlisteners.delete(id);
+      off();
+    }
+  }
+
+  function emit(event, payload) {
+    bevy.send(event, payload === undefined ? null : payload);
+    return Promise.resolve();
+  }
+
+  // Used by `@tauri-apps/api` npm package.
+  window.__TAURI_INTERNALS__ = {
+    invoke,
+    transformCallback(callback, once) {
+      const id = nextId++;
+      callbacks.set(id, (response) => {
+        if (once) {
+          callbacks.delete(id);
+        }
+
+        return callback && callback(response);
+      });
+      return id;
+    },
+  };
+
+  window.__TAURI_EVENT_PLUGIN_INTERNALS__ = {
+    unregisterListener(_event, id) {
+      unlisten(id);
+    },
+  };
+
+  const event = {
+    listen(event, handler) {
+      const id = listen(event, handler);
+      return Promise.resolve(() => unlisten(id));
+    },
+    once(event, handler) {
+      const id = listen(event, (e) => {
+        unlisten(id);
+        handler(e);
+      });
+      return Promise.resolve(() => unlisten(id));
+    },
+    emit,
+  };
+
+  // `withGlobalTauri` API of Tauri 2, with Tauri 1 aliases.
+  window.__TAURI__ = {
+    core: { invoke },
+    event,
+    invoke,
+    tauri: { invoke },
+  };
+})();
@@ -1,29 +1,109 @@
+pub mod client;
 pub mod components;
 mod error;
 pub mod events;
+pub mod protocol;
 pub mod systems;
+pub mod typescript;
 
+use std::time::Duration;
+
+use bevy::ecs::schedule::{InternedScheduleLabel, ScheduleLabel};
 use bevy::prelude::*;
-use components::webview::WebViews;
-use events::{InWryEvent, OutWryEvent};
+use bevy::render::{Render, RenderApp, RenderSet};
+use bevy::state::app::StatesPlugin;
+use components::webview::{WebViewFailed, WebViewNames, WebViews};
+use events::error::WryIpcError;
+use events::lifecycle::{
+    is_page_tag, NavigationBlocked, NavigationRequested, NewWindowRequested, PageLoad,
+    PageLoadFinished, PageLoadStarted, ReadyHandshake, WebViewDestroyed, WebViewJsError,
+    WebViewReady, JS_ERROR_TAG, READY_TAG,
+};
+use events::query::{JsQueries, JsQueryResponse, JS_QUERY_TAG};
+use events::request::{InWryRequest, WryRequest};
+use events::{default_tag, InWryEvent, IncomingEventTrigger, IncomingEvents, OutWryEvent};
+use protocol::api::{ApiRequest, ApiResponse, ApiRoutes};
+use protocol::embedded::UiBundles;
+use protocol::ProtocolRequests;
+use typescript::{WryTypeKind, WryTypes};
 
-use systems::events::{consume_ipfs_events, produce_out_scripts};
+pub use error::Error;
+pub use rust_embed;
+use systems::events::{
+    consume_ipc_messages, expire_js_queries, log_js_errors, produce_out_scripts,
+    resolve_js_queries, trigger_incoming_event, trigger_incoming_request,
+};
+use systems::webview::{WryDevtools, WryTauriShim};
+use systems::{FramePresented, WrySet, WryStartupDelay, WryStartupState, WryStartupTimeout};
 pub use wry;
 pub use wry::dpi::{Position as WryPosition, Size as WrySize};
 
 /// [Resource] storing url used by [wry::WebView].
-// TODO: This can be modified to change the url at runtime.
-#[derive(Resource, Deref, Clone, Default)]
+///
+/// Not read by bevy_wry anymore, set [components::webview::Source] of the webview entity or
+/// queue [components::webview::Navigate] instead.
+#[deprecated(note = "use Source / Navigate")]
+#[derive(Clone, Default)]
 pub struct UrlResource(pub String);
 
+#[allow(deprecated)]
+impl Resource for UrlResource {}
+
+#[allow(deprecated)]
+impl std::ops::Deref for UrlResource {
+    type Target = String;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Plugin creating [wry::WebView]s for [components::webview::WebViewComponent] entities.
+///
+/// Requires [StatesPlugin], added by [DefaultPlugins].
+///
+/// Example
+/// ```rust
+/// use std::time::Duration;
+///
+/// use bevy::prelude::*;
+/// use bevy_wry::{BevyWryPlugin, WryAppExt};
+///
+/// #[derive(Event, serde::Deserialize)]
+/// struct StartGame;
+///
+/// fn run_app() {
+///     App::new()
+///         .add_plugins(DefaultPlugins)
+///         .add_plugins(
+///             BevyWryPlugin::default()
+///                 .with_devtools(cfg!(debug_assertions))
+///                 .with_startup_delay(Duration::from_millis(100)),
+///         )
+///         .add_wry_incoming::<StartGame>()
+///         .run();
+/// }
+/// ```
 pub struct BevyWryPlugin {
-    setup_callback: fn(&mut App),
+    setup_callback: Option<Box<dyn Fn(&mut App) + Send + Sync>>,
+    startup_delay: Duration,
+    startup_timeout: Duration,
+    schedule: InternedScheduleLabel,
+    flush_schedule: InternedScheduleLabel,
+    devtools: bool,
+    tauri_shim: bool,
+    init_gtk: bool,
+    disable_dmabuf_renderer: bool,
 }
 
 /// Register event type that will be triggered by incoming messages from [wry::WebView].
-/// This function should be called in [BevyWryPlugin] setup callback.
+/// This function can be called from any plugin, see also [WryAppExt::add_wry_incoming].
 ///
 /// Events registered this way can be received via observer pattern.
+/// The event is registered under [default_tag], so the webview has to wrap
+/// its payload in an [events::IpcEnvelope]:
+/// `window.ipc.postMessage(JSON.stringify({ type: "IpfsInCommand", payload: { Print: "hi" } }))`.
+/// Use [register_incoming_event_with_tag] to pick a different tag.
 ///
 /// Example
 /// ```rust
@@ -64,14 +144,89 @@ pub fn register_incoming_event<E>(app: &mut App)
 where
     for<'de> E: InWryEvent<'de>,
 {
-    app.add_event::<E>()
-        .add_systems(Update, consume_ipfs_events::<E>);
+    register_incoming_event_with_tag::<E>(app, default_tag::<E>());
+}
+
+/// Same as [register_incoming_event], but the event is registered under a custom `tag`.
+///
+/// # Panics
+/// Panics if another event type is already registered under `tag`, or `tag` starts with
+/// [events::lifecycle::RESERVED_TAG_PREFIX].
+pub fn register_incoming_event_with_tag<E>(app: &mut App, tag: impl Into<String>)
+where
+    for<'de> E: InWryEvent<'de>,
+{
+    let tag = tag.into();
+    app.add_event::<E>();
+    record_type::<E>(app, tag.clone(), WryTypeKind::Incoming);
+    insert_incoming_trigger(app, tag, trigger_incoming_event::<E>);
+}
+
+/// Register request type that can be sent from [wry::WebView] with `bevy.invoke`.
+/// This function can be called from any plugin, see also [WryAppExt::add_wry_request].
+///
+/// Requests are triggered as [WryRequest] and the `Promise` returned by `bevy.invoke`
+/// settles once the observer responds. The request is registered under [default_tag].
+///
+/// Example
+/// ```rust
+/// use bevy::prelude::*;
+/// use bevy_wry::events::request::{InWryRequest, WryRequest};
+/// use bevy_wry::{register_request, BevyWryPlugin};
+///
+/// // JS: `const items = await bevy.invoke("GetInventory", { slot: 2 });`
+/// #[derive(serde::Deserialize)]
+/// struct GetInventory {
+///     slot: usize,
+/// }
+/// impl InWryRequest for GetInventory {
+///     type Response = Vec<String>;
+/// }
+///
+/// fn run_app() {
+///     App::new()
+///         .add_plugins(DefaultPlugins)
+///         .add_plugins(BevyWryPlugin::new(|app| {
+///             register_request::<GetInventory>(app);
+///         }))
+///         .add_observer(get_inventory)
+///         .run();
+/// }
+///
+/// fn get_inventory(trigger: Trigger<WryRequest<GetInventory>>) {
+///     let request = trigger.event();
+///     if request.request.slot > 3 {
+///         request.reject("no such slot");
+///     } else {
+///         request.respond(vec!["sword".to_string()]);
+///     }
+/// }
+/// ```
+pub fn register_request<R: InWryRequest>(app: &mut App) {
+    register_request_with_tag::<R>(app, default_tag::<R>());
+}
+
+/// Same as [register_request], but the request is registered under a custom `tag`.
+///
+/// # Panics
+/// Panics if another event or request type is already registered under `tag`, or `tag`
+/// starts with [events::lifecycle::RESERVED_TAG_PREFIX].
+pub fn register_request_with_tag<R: InWryRequest>(app: &mut App, tag: impl Into<String>) {
+    let tag = tag.into();
+    let kind = WryTypeKind::Request {
+        response: std::any::TypeId::of::<R::Response>(),
+        response_name: std::any::type_name::<R::Response>(),
+    };
+    app.add_event::<WryRequest<R>>();
+    record_type::<R>(app, tag.clone(), kind);
+    insert_incoming_trigger(app, tag, trigger_incoming_request::<R>);
 }
 
 /// Register event type that will be sent to [wry::WebView].
-/// This function should be called in [BevyWryPlugin] setup callback.
+/// This function can be called from any plugin, see also [WryAppExt::add_wry_outgoing].
 ///
 /// Events registered this will be received via observer pattern.
+/// By default the page receives them via `bevy.on(tag, handler)`, see [OutWryEvent].
 ///
 /// Example
 /// ```rust
@@ -80,14 +235,10 @@ where
 /// use bevy_wry::components::webview::WebViewComponent;
 /// use bevy_wry::events::OutWryEvent;
 ///
-/// // Simple event that will be calling `console.log` with provided message.
+/// // Received in JS with: `bevy.on("ConsoleLog", (msg) => console.log(msg))`
 /// #[derive(Event, Clone, serde::Serialize)]
 /// struct ConsoleLog(String);
-/// impl OutWryEvent for ConsoleLog {
-///     fn to_script(&self) -> String {
-///         format!("console.log({})", self.0)
-///     }
-/// }
+/// impl OutWryEvent for ConsoleLog {}
 ///
 /// fn run_app() {
 ///     App::new()
@@ -110,30 +261,391 @@ where
 /// ```
 pub fn register_out_event<E: OutWryEvent>(app: &mut App) {
     app.add_event::<E>().add_observer(produce_out_scripts::<E>);
+    record_type::<E>(app, E::tag().to_string(), WryTypeKind::Outgoing);
+}
+
+/// Serve web assets embedded with [rust_embed::RustEmbed] as `bevy://<name>/<path>`,
+/// see [protocol::bundle_url]. Select the bundle with
+/// [components::webview::WebViewBundleBuilder::with_bundle].
+///
+/// Debug builds read the files from disk, so the UI can be rebuilt without recompiling
+/// the game, and release builds embed them in the binary. Urls are the same in both.
+///
+/// # Panics
+/// Panics if another bundle is already registered as `name`.
+///
+/// Example
+/// ```rust,ignore
+/// use bevy::prelude::*;
+/// use bevy_wry::rust_embed::RustEmbed;
+/// use bevy_wry::{BevyWryPlugin, WryAppExt};
+///
+/// // Trunk output, relative to Cargo.toml
+/// #[derive(RustEmbed)]
+/// #[folder = "ui/dist"]
+/// #[crate_path = "bevy_wry::rust_embed"]
+/// struct Ui;
+///
+/// fn run_app() {
+///     App::new()
+///         .add_plugins(DefaultPlugins)
+///         .add_plugins(BevyWryPlugin::default())
+///         .add_wry_ui_bundle::<Ui>("ui")
+///         .run();
+/// }
+/// ```
+pub fn register_ui_bundle<E: rust_embed::RustEmbed>(app: &mut App, name: impl Into<String>) {
+    app.init_resource::<UiBundles>();
+    app.world_mut()
+        .resource_mut::<UiBundles>()
+        .insert::<E>(name.into());
+}
+
+/// Answer `fetch` requests to `bevy://api/<path>` (see [protocol::api_url]) made with `method`
+/// by running `handler` system. This function can be called from any plugin,
+/// see also [WryAppExt::add_wry_route].
+///
+/// `path` segments starting with `:` match any value, available with [ApiRequest::param].
+/// Handlers run in [systems::WrySet::ReceiveIpc] with exclusive access to the [World],
+/// so they can query any data the UI needs. Only pages served by bevy_wry custom protocols
+/// can call the routes, allow other origins (e.g. a dev server) with [register_api_origin].
+///
+/// # Panics
+/// Panics if `method` is invalid or the route is already registered.
+///
+/// Example
+/// ```rust
+/// use bevy::prelude::*;
+/// use bevy_wry::protocol::api::{ApiRequest, ApiResponse};
+/// use bevy_wry::wry::http::StatusCode;
+/// use bevy_wry::{BevyWryPlugin, WryAppExt};
+///
+/// #[derive(Component, Clone, serde::Serialize)]
+/// struct Item {
+///     name: String,
+/// }
+///
+/// // JS: `const items = await (await fetch("bevy://api/inventory")).json();`
+/// fn inventory(_: In<ApiRequest>, items: Query<&Item>) -> ApiResponse {
+///     ApiResponse::json(&items.iter().cloned().collect::<Vec<_>>())
+/// }
+///
+/// // JS: `await fetch("bevy://api/inventory/3")`
+/// fn item(request: In<ApiRequest>, items: Query<&Item>) -> ApiResponse {
+///     let item = request.param("index").and_then(|index| index.parse().ok());
+///     match item.and_then(|index: usize| items.iter().nth(index)) {
+///         Some(item) => ApiResponse::json(item),
+///         None => ApiResponse::status(StatusCode::NOT_FOUND, "no such item"),
+///     }
+/// }
+///
+/// fn run_app() {
+///     App::new()
+///         .add_plugins(DefaultPlugins)
+///         .add_plugins(BevyWryPlugin::default())
+///         .add_wry_route("GET", "/inventory", inventory)
+///         .add_wry_route("GET", "/inventory/:index", item)
+///         .run();
+/// }
+/// ```
+pub fn register_route<M>(
+    app: &mut App,
+    method: &str,
+    path: &str,
+    handler: impl IntoSystem<In<ApiRequest>, ApiResponse, M> + 'static,
+) {
+    app.init_resource::<ApiRoutes>();
+    let handler = app.register_system(handler);
+    app.world_mut()
+        .resource_mut::<ApiRoutes>()
+        .insert(method, path, handler);
+}
+
+/// Allow pages from `origin` (e.g. `http://localhost:8080`) to call routes registered with
+/// [register_route]. Pages served by bevy_wry custom protocols are always allowed.
+/// See also [WryAppExt::add_wry_api_origin].
+///
+/// Allowed pages can read and change the game state through the routes, so never allow
+/// origins serving untrusted content.
+pub fn register_api_origin(app: &mut App, origin: impl Into<String>) {
+    app.init_resource::<ApiRoutes>();
+    app.world_mut()
+        .resource_mut::<ApiRoutes>()
+        .allow_origin(origin.into());
+}
+
+/// [App] extension registering bevy_wry types, usable from any plugin.
+///
+/// Example
+/// ```rust
+/// use bevy::prelude::*;
+/// use bevy_wry::WryAppExt;
+///
+/// #[derive(Event, serde::Deserialize)]
+/// struct OpenInventory;
+///
+/// struct InventoryUiPlugin;
+///
+/// impl Plugin for InventoryUiPlugin {
+///     fn build(&self, app: &mut App) {
+///         app.add_wry_incoming::<OpenInventory>();
+///     }
+/// }
+/// ```
+pub trait WryAppExt {
+    /// See [register_incoming_event].
+    fn add_wry_incoming<E>(&mut self) -> &mut Self
+    where
+        for<'de> E: InWryEvent<'de>;
+
+    /// See [register_incoming_event_with_tag].
+    fn add_wry_incoming_with_tag<E>(&mut self, tag: impl Into<String>) -> &mut Self
+    where
+        for<'de> E: InWryEvent<'de>;
+
+    /// See [register_request].
+    fn add_wry_request<R: InWryRequest>(&mut self) -> &mut Self;
+
+    /// See [register_request_with_tag].
+    fn add_wry_request_with_tag<R: InWryRequest>(&mut self, tag: impl Into<String>) -> &mut Self;
+
+    /// See [register_out_event].
+    fn add_wry_outgoing<E: OutWryEvent>(&mut self) -> &mut Self;
+
+    /// See [register_ui_bundle].
+    fn add_wry_ui_bundle<E: rust_embed::RustEmbed>(&mut self, name: impl Into<String>)
+        -> &mut Self;
+
+    /// See [register_route].
+    fn add_wry_route<M>(
+        &mut self,
+        method: &str,
+        path: &str,
+        handler: impl IntoSystem<In<ApiRequest>, ApiResponse, M> + 'static,
+    ) -> &mut Self;
+
+    /// See [register_api_origin].
+    fn add_wry_api_origin(&mut self, origin: impl Into<String>) -> &mut Self;
+}
+
+impl WryAppExt for App {
+    fn add_wry_incoming<E>(&mut self) -> &mut Self
+    where
+        for<'de> E: InWryEvent<'de>,
+    {
+        register_incoming_event::<E>(self);
+        self
+    }
+
+    fn add_wry_incoming_with_tag<E>(&mut self, tag: impl Into<String>) -> &mut Self
+    where
+        for<'de> E: InWryEvent<'de>,
+    {
+        register_incoming_event_with_tag::<E>(self, tag);
+        self
+    }
+
+    fn add_wry_request<R: InWryRequest>(&mut self) -> &mut Self {
+        register_request::<R>(self);
+        self
+    }
+
+    fn add_wry_request_with_tag<R: InWryRequest>(&mut self, tag: impl Into<String>) -> &mut Self {
+        register_request_with_tag::<R>(self, tag);
+        self
+    }
+
+    fn add_wry_outgoing<E: OutWryEvent>(&mut self) -> &mut Self {
+        register_out_event::<E>(self);
+        self
+    }
+
+    fn add_wry_ui_bundle<E: rust_embed::RustEmbed>(
+        &mut self,
+        name: impl Into<String>,
+    ) -> &mut Self {
+        register_ui_bundle::<E>(self, name);
+        self
+    }
+
+    fn add_wry_route<M>(
+        &mut self,
+        method: &str,
+        path: &str,
+        handler: impl IntoSystem<In<ApiRequest>, ApiResponse, M> + 'static,
+    ) -> &mut Self {
+        register_route(self, method, path, handler);
+        self
+    }
+
+    fn add_wry_api_origin(&mut self, origin: impl Into<String>) -> &mut Self {
+        register_api_origin(self, origin);
+        self
+    }
+}
+
+fn insert_incoming_trigger(app: &mut App, tag: String, trigger: IncomingEventTrigger) {
+    if !is_page_tag(&tag) {
+        panic!("incoming event tag '{tag}' is reserved for bevy_wry");
+    }
+
+    app.init_resource::<IncomingEvents>();
+    app.world_mut()
+        .resource_mut::<IncomingEvents>()
+        .insert(tag, trigger);
+}
+
+/// Remember registered type, so it can be described with [typescript::typescript_declarations].
+fn record_type<T: 'static>(app: &mut App, tag: String, kind: WryTypeKind) {
+    app.init_resource::<WryTypes>();
+    app.world_mut()
+        .resource_mut::<WryTypes>()
+        .push::<T>(tag, kind);
+}
+
+impl Default for BevyWryPlugin {
+    fn default() -> Self {
+        Self {
+            setup_callback: None,
+            startup_delay: Duration::ZERO,
+            startup_timeout: WryStartupTimeout::default().0,
+            schedule: Update.intern(),
+            flush_schedule: PostUpdate.intern(),
+            devtools: false,
+            tauri_shim: false,
+            init_gtk: true,
+            disable_dmabuf_renderer: true,
+        }
+    }
 }
 
 impl BevyWryPlugin {
-    pub fn new(setup_callback: fn(&mut App)) -> Self {
-        Self { setup_callback }
+    /// Plugin calling `setup_callback` at the end of [Plugin::build].
+    pub fn new(setup_callback: impl Fn(&mut App) + Send + Sync + 'static) -> Self {
+        Self::default().with_setup(setup_callback)
+    }
+
+    /// Call `setup_callback` at the end of [Plugin::build], e.g. to register event types.
+    /// Types can be registered from any plugin with [WryAppExt] as well.
+    pub fn with_setup(mut self, setup_callback: impl Fn(&mut App) + Send + Sync + 'static) -> Self {
+        self.setup_callback = Some(Box::new(setup_callback));
+        self
+    }
+
+    /// Enable webview devtools. Default: false
+    pub fn with_devtools(mut self, devtools: bool) -> Self {
+        self.devtools = devtools;
+        self
+    }
+
+    /// Inject Tauri compatibility shim, so frontends written for Tauri can call
+    /// `window.__TAURI__.core.invoke` and `listen`, see [client]. Default: false
+    pub fn with_tauri_shim(mut self, tauri_shim: bool) -> Self {
+        self.tauri_shim = tauri_shim;
+        self
+    }
+
+    /// Initialize GTK on Linux and BSD. Disable if the app initializes GTK itself. Default: true
+    pub fn with_gtk_init(mut self, init_gtk: bool) -> Self {
+        self.init_gtk = init_gtk;
+        self
+    }
+
+    /// Set `WEBKIT_DISABLE_DMABUF_RENDERER=1` on Linux and BSD, working around
+    /// https://github.com/tauri-apps/tauri/issues/9304. Default: true
+    pub fn with_dmabuf_renderer_disabled(mut self, disable_dmabuf_renderer: bool) -> Self {
+        self.disable_dmabuf_renderer = disable_dmabuf_renderer;
+        self
+    }
+
+    /// Delay creation of webviews after the first frame of the primary window is presented,
+    /// on platforms where [WryStartupState] detection is not enough. Default: zero
+    pub fn with_startup_delay(mut self, delay: Duration) -> Self {
+        self.startup_delay = delay;
+        self
+    }
+
+    /// Create webviews `timeout` after app start even if no frame of the primary window was
+    /// presented, e.g. without `RenderPlugin`. Default: 1 second
+    pub fn with_startup_timeout(mut self, timeout: Duration) -> Self {
+        self.startup_timeout = timeout;
+        self
+    }
+
+    /// Schedule of [WrySet::ReceiveIpc], [WrySet::Create] and [WrySet::Layout]. Default: [Update]
+    pub fn with_schedule(mut self, schedule: impl ScheduleLabel) -> Self {
+        self.schedule = schedule.intern();
+        self
+    }
+
+    /// Schedule of [WrySet::FlushIpc]. Default: [PostUpdate]
+    pub fn with_flush_schedule(mut self, schedule: impl ScheduleLabel) -> Self {
+        self.flush_schedule = schedule.intern();
+        self
     }
 }
 
 impl Plugin for BevyWryPlugin {
     fn build(&self, app: &mut App) {
         let app = app
+            .insert_resource(WryStartupDelay(self.startup_delay))
+            .insert_resource(WryStartupTimeout(self.startup_timeout))
+            .insert_resource(WryDevtools(self.devtools))
+            .insert_resource(WryTauriShim(self.tauri_shim))
+            .init_resource::<FramePresented>()
             .insert_non_send_resource(WebViews::default())
+            .init_resource::<WebViewNames>()
+            .init_resource::<ProtocolRequests>()
+            .init_resource::<UiBundles>()
+            .init_resource::<ApiRoutes>()
+            .init_resource::<IncomingEvents>()
+            .add_event::<WryIpcError>()
+            .init_resource::<JsQueries>()
+            .add_observer(resolve_js_queries)
+            .add_observer(systems::webview::handle_ready_handshake)
+            .add_observer(systems::webview::handle_page_load)
+            .add_observer(log_js_errors)
+            .configure_sets(self.schedule, (WrySet::Create, WrySet::Layout).chain())
             .add_systems(
-                Update,
+                self.schedule,
                 (
-                    systems::webview::create_webviews,
-                    systems::webview::keep_webviews_in_bounds,
+                    consume_ipc_messages,
+                    expire_js_queries,
+                    systems::protocol::serve_protocol_requests,
+                    systems::protocol::serve_api_requests,
                 )
-                    // FIXME: After wgpu update (22 -> 23), bevy commit: 4b05d2f4
-                    // rendering doesn't work without this small delay. I narrowed
-                    // it down to this wgpu commit: fb0cb1eb
-                    .run_if(systems::boot_delay_elapsed),
+                    .in_set(WrySet::ReceiveIpc),
             )
-            .add_systems(PostUpdate, systems::events::clear_busses);
+            .add_systems(
+                self.schedule,
+                (
+                    systems::detect_startup.run_if(in_state(WryStartupState::WaitingForWindow)),
+                    (
+                        systems::webview::sync_navigation_policies,
+                        systems::webview::create_webviews,
+                        systems::webview::navigate_webviews,
+                    )
+                        .chain()
+                        .run_if(in_state(WryStartupState::Ready)),
+                )
+                    .in_set(WrySet::Create),
+            )
+            .add_systems(
+                self.schedule,
+                systems::webview::keep_webviews_in_bounds
+                    .run_if(in_state(WryStartupState::Ready))
+                    .in_set(WrySet::Layout),
+            )
+            .add_systems(
+                self.flush_schedule,
+                systems::events::clear_busses.in_set(WrySet::FlushIpc),
+            );
+
+        #[cfg(feature = "image")]
+        app.add_systems(
+            self.schedule,
+            systems::protocol::serve_image_requests.in_set(WrySet::ReceiveIpc),
+        );
 
         #[cfg(any(
             target_os = "linux",
@@ -143,9 +655,14 @@ impl Plugin for BevyWryPlugin {
             target_os = "openbsd",
         ))]
         {
-            // https://github.com/tauri-apps/tauri/issues/9304
-            std::env::set_var("WEBKIT_DISABLE_DMABUF_RENDERER", "1");
-            gtk::init().unwrap();
+            if self.disable_dmabuf_renderer {
+                // https://github.com/tauri-apps/tauri/issues/9304
+                std::env::set_var("WEBKIT_DISABLE_DMABUF_RENDERER", "1");
+            }
+
+            if self.init_gtk {
+                gtk::init().unwrap();
+            }
 
             // we need to ignore this error here otherwise it will be catched by winit and will be
             // make the example crash
@@ -154,11 +671,56 @@ impl Plugin for BevyWryPlugin {
                 (unsafe { (*error).error_code }) == 170
             }));
 
-            let app = app.add_systems(Update, gtk_iteration_do);
+            let app = app.add_systems(self.schedule, gtk_iteration_do.before(WrySet::ReceiveIpc));
+        }
+
+        app.add_event::<JsQueryResponse>()
+            .add_event::<ReadyHandshake>()
+            .add_event::<PageLoad>()
+            .add_event::<PageLoadStarted>()
+            .add_event::<PageLoadFinished>()
+            .add_event::<NavigationRequested>()
+            .add_event::<NavigationBlocked>()
+            .add_event::<NewWindowRequested>()
+            .add_event::<WebViewReady>()
+            .add_event::<WebViewFailed>()
+            .add_event::<WebViewDestroyed>()
+            .add_event::<WebViewJsError>();
+        insert_incoming_trigger(
+            app,
+            JS_QUERY_TAG.to_string(),
+            trigger_incoming_event::<JsQueryResponse>,
+        );
+        insert_incoming_trigger(
+            app,
+            READY_TAG.to_string(),
+            trigger_incoming_event::<ReadyHandshake>,
+        );
+        insert_incoming_trigger(
+            app,
+            JS_ERROR_TAG.to_string(),
+            trigger_incoming_event::<WebViewJsError>,
+        );
+
+        if let Some(setup) = &self.setup_callback {
+            setup(app);
         }
+    }
 
-        let setup = self.setup_callback;
-        setup(app);
+    fn finish(&self, app: &mut App) {
+        // Checked here, so BevyWryPlugin can be added before DefaultPlugins
+        assert!(
+            app.is_plugin_added::<StatesPlugin>(),
+            "BevyWryPlugin requires StatesPlugin, add DefaultPlugins or StatesPlugin"
+        );
+        app.init_state::<WryStartupState>();
+
+        let presented = app.world().resource::<FramePresented>().clone();
+        if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app
+                .insert_resource(presented)
+                .add_systems(Render, systems::detect_present.in_set(RenderSet::Queue));
+        }
     }
 }
 
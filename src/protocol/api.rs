@@ -0,0 +1,377 @@
+use std::borrow::Cow;
+
+use bevy::ecs::system::SystemId;
+use bevy::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use wry::http::header::{
+    ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN,
+    CONTENT_TYPE, VARY,
+};
+use wry::http::{Method, Response, StatusCode};
+
+use super::{percent_decode, ProtocolRequest, BEVY_SCHEME};
+
+/// System handling requests to one route, see [crate::register_route].
+pub type RouteHandler = SystemId<In<ApiRequest>, ApiResponse>;
+
+/// Request made with `fetch("bevy://api/...")`, passed to the route handler.
+#[derive(Debug, Clone)]
+pub struct ApiRequest {
+    /// Webview making the request
+    pub webview: Entity,
+    pub method: Method,
+    /// Request path, e.g. `/inventory/2`
+    pub path: String,
+    /// Values of `:name` segments of the route, in order of the route
+    pub params: Vec<(String, String)>,
+    pub query: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl ApiRequest {
+    /// Value of `:name` segment of the route, e.g. `id` of `/inventory/:id`.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        find(&self.params, name)
+    }
+
+    /// Value of query string parameter `name`.
+    pub fn query(&self, name: &str) -> Option<&str> {
+        find(&self.query, name)
+    }
+
+    /// Deserialize JSON body.
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        serde_json::from_slice(&self.body)
+    }
+}
+
+fn find<'a>(pairs: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    pairs
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.as_str())
+}
+
+/// Response of the route handler.
+#[derive(Debug, Clone)]
+pub struct ApiResponse {
+    pub status: StatusCode,
+    pub content_type: String,
+    pub body: Vec<u8>,
+}
+
+impl ApiResponse {
+    /// `200 OK` with serialized `value`. Serialization errors are returned as `500`.
+    pub fn json<T: Serialize>(value: &T) -> Self {
+        match serde_json::to_vec(value) {
+            Ok(body) => Self::bytes("application/json", body),
+            Err(e) => Self::status(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        }
+    }
+
+    /// `200 OK` with `body` of `content_type`.
+    pub fn bytes(content_type: impl Into<String>, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status: StatusCode::OK,
+            content_type: content_type.into(),
+            body: body.into(),
+        }
+    }
+
+    /// Response with `status` and plain text `message`, e.g. `404 Not Found`.
+    pub fn status(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            content_type: "text/plain".to_string(),
+            body: message.into().into_bytes(),
+        }
+    }
+
+    /// Change the status, e.g. `ApiResponse::json(&item).with_status(StatusCode::CREATED)`.
+    pub fn with_status(mut self, status: StatusCode) -> Self {
+        self.status = status;
+        self
+    }
+}
+
+struct ApiRoute {
+    method: Method,
+    segments: Vec<String>,
+    handler: RouteHandler,
+}
+
+/// [Resource] of routes registered with [crate::register_route].
+///
+/// Pages served by bevy_wry custom protocols can call the routes. Pages from other origins
+/// (e.g. a dev server) have to be allowed with [crate::register_api_origin]. Requests from
+/// any other origin, or without `Origin` header, are rejected with `403 Forbidden`.
+#[derive(Resource, Default)]
+pub struct ApiRoutes {
+    routes: Vec<ApiRoute>,
+    allowed_origins: Vec<String>,
+}
+
+/// Result of matching request against registered routes.
+pub(crate) enum RouteMatch {
+    Found(RouteHandler, Vec<(String, String)>),
+    MethodNotAllowed,
+    NotFound,
+}
+
+impl ApiRoutes {
+    /// # Panics
+    /// Panics if `method` is invalid or the route is already registered.
+    pub(crate) fn insert(&mut self, method: &str, path: &str, handler: RouteHandler) {
+        let method = Method::from_bytes(method.to_ascii_uppercase().as_bytes())
+            .unwrap_or_else(|_| panic!("Invalid HTTP method '{method}'"));
+        let segments = segments(path).map(str::to_string).collect::<Vec<_>>();
+        if self
+            .routes
+            .iter()
+            .any(|route| route.method == method && route.segments == segments)
+        {
+            panic!("Route {method} '{path}' is already registered");
+        }
+
+        self.routes.push(ApiRoute {
+            method,
+            segments,
+            handler,
+        });
+    }
+
+    pub(crate) fn allow_origin(&mut self, origin: String) {
+        self.allowed_origins
+            .push(origin.trim_end_matches('/').to_string());
+    }
+
+    /// Returns `true` if pages from `origin` can call the routes.
+    pub fn is_allowed_origin(&self, origin: &str) -> bool {
+        is_bevy_origin(origin) || self.allowed_origins.iter().any(|allowed| allowed == origin)
+    }
+
+    /// Methods of routes matching `path`, for CORS preflight.
+    pub(crate) fn methods(&self, path: &str) -> Vec<Method> {
+        let path = segments(path).collect::<Vec<_>>();
+        self.routes
+            .iter()
+            .filter(|route| match_segments(&route.segments, &path).is_some())
+            .map(|route| route.method.clone())
+            .collect()
+    }
+
+    pub(crate) fn find(&self, method: &Method, path: &str) -> RouteMatch {
+        let path = segments(path).collect::<Vec<_>>();
+        let mut result = RouteMatch::NotFound;
+        for route in &self.routes {
+            let Some(params) = match_segments(&route.segments, &path) else {
+                continue;
+            };
+
+            if route.method != *method {
+                result = RouteMatch::MethodNotAllowed;
+                continue;
+            }
+
+            return RouteMatch::Found(route.handler, params);
+        }
+
+        result
+    }
+}
+
+/// Origin of pages served by bevy_wry custom protocols, e.g. `bevy://ui`.
+/// On Windows and Android custom protocols are exposed as `http://bevy.<host>`.
+fn is_bevy_origin(origin: &str) -> bool {
+    let host = if cfg!(any(target_os = "windows", target_os = "android")) {
+        origin
+            .strip_prefix("http://")
+            .or_else(|| origin.strip_prefix("https://"))
+            .and_then(|host| host.strip_prefix(BEVY_SCHEME))
+            .and_then(|host| host.strip_prefix('.'))
+    } else {
+        origin
+            .strip_prefix(BEVY_SCHEME)
+            .and_then(|host| host.strip_prefix("://"))
+    };
+
+    host.is_some_and(|host| !host.is_empty() && !host.contains(['/', ':', '@']))
+}
+
+fn segments(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|segment| !segment.is_empty())
+}
+
+/// Params of `:name` segments, if `path` matches `route`.
+fn match_segments(route: &[String], path: &[&str]) -> Option<Vec<(String, String)>> {
+    if route.len() != path.len() {
+        return None;
+    }
+
+    let mut params = Vec::new();
+    for (route, path) in route.iter().zip(path) {
+        match route.strip_prefix(':') {
+            Some(name) => params.push((name.to_string(), percent_decode(path))),
+            None if route == path => {}
+            None => return None,
+        }
+    }
+
+    Some(params)
+}
+
+/// Build [ApiRequest] of the protocol request. Path params are filled in by the router.
+pub(crate) fn api_request(request: &ProtocolRequest) -> ApiRequest {
+    let uri = request.request.uri();
+    let query = uri
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let decode = |s: &str| percent_decode(&s.replace('+', " "));
+            (decode(key), decode(value))
+        })
+        .collect();
+
+    ApiRequest {
+        webview: request.webview,
+        method: request.request.method().clone(),
+        path: uri.path().to_string(),
+        params: Vec::new(),
+        query,
+        body: request.request.body().clone(),
+    }
+}
+
+/// Pages are usually served from another origin than `bevy://api`, so responses allow
+/// the request `origin`, already checked with [ApiRoutes::is_allowed_origin].
+pub(crate) fn api_response(
+    response: ApiResponse,
+    origin: Option<&str>,
+) -> Response<Cow<'static, [u8]>> {
+    cors(Response::builder(), origin)
+        .status(response.status)
+        .header(CONTENT_TYPE, response.content_type)
+        .body(Cow::Owned(response.body))
+        .unwrap()
+}
+
+/// Response to CORS preflight `OPTIONS` request, allowing `methods` of the route.
+pub(crate) fn preflight_response(
+    origin: Option<&str>,
+    methods: &[Method],
+) -> Response<Cow<'static, [u8]>> {
+    let methods = methods
+        .iter()
+        .map(Method::as_str)
+        .collect::<Vec<_>>()
+        .join(", ");
+    cors(Response::builder(), origin)
+        .status(StatusCode::NO_CONTENT)
+        .header(ACCESS_CONTROL_ALLOW_METHODS, methods)
+        .header(ACCESS_CONTROL_ALLOW_HEADERS, "Content-Type")
+        .body(Cow::Borrowed(&[][..]))
+        .unwrap()
+}
+
+fn cors(
+    response: wry::http::response::Builder,
+    origin: Option<&str>,
+) -> wry::http::response::Builder {
+    let response = response.header(VARY, "Origin");
+    match origin {
+        Some(origin) => response.header(ACCESS_CONTROL_ALLOW_ORIGIN, origin),
+        None => response,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(path: &str) -> Vec<String> {
+        segments(path).map(str::to_string).collect()
+    }
+
+    fn matches(route_path: &str, path: &str) -> Option<Vec<(String, String)>> {
+        let path = segments(path).collect::<Vec<_>>();
+        match_segments(&route(route_path), &path)
+    }
+
+    fn params(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn static_routes_match_exactly() {
+        assert_eq!(matches("/inventory", "/inventory"), Some(Vec::new()));
+        assert_eq!(matches("/inventory", "inventory/"), Some(Vec::new()));
+        assert_eq!(matches("/inventory", "/inventory/2"), None);
+        assert_eq!(matches("/inventory", "/items"), None);
+        assert_eq!(matches("/", "/"), Some(Vec::new()));
+    }
+
+    #[test]
+    fn params_capture_decoded_segments() {
+        assert_eq!(
+            matches("/inventory/:slot", "/inventory/2"),
+            Some(params(&[("slot", "2")]))
+        );
+        assert_eq!(
+            matches(
+                "/players/:name/items/:id",
+                "/players/Jan%20Kowalski/items/7"
+            ),
+            Some(params(&[("name", "Jan Kowalski"), ("id", "7")]))
+        );
+        assert_eq!(matches("/inventory/:slot", "/inventory"), None);
+        assert_eq!(matches("/inventory/:slot", "/inventory/2/3"), None);
+        assert_eq!(matches("/players/:name/items", "/players/jan/skills"), None);
+    }
+
+    #[test]
+    fn find_distinguishes_methods() {
+        let mut world = World::new();
+        let handler = world.register_system(|_: In<ApiRequest>| ApiResponse::bytes("", ""));
+        let mut routes = ApiRoutes::default();
+        routes.insert("get", "/inventory/:slot", handler);
+
+        let found = routes.find(&Method::GET, "/inventory/1");
+        assert!(
+            matches!(found, RouteMatch::Found(id, params) if id == handler && params.len() == 1)
+        );
+        assert!(matches!(
+            routes.find(&Method::POST, "/inventory/1"),
+            RouteMatch::MethodNotAllowed
+        ));
+        assert!(matches!(
+            routes.find(&Method::GET, "/items"),
+            RouteMatch::NotFound
+        ));
+        assert_eq!(routes.methods("/inventory/1"), vec![Method::GET]);
+    }
+
+    #[test]
+    fn only_bevy_and_allowed_origins_are_allowed() {
+        let mut routes = ApiRoutes::default();
+        routes.allow_origin("http://localhost:8080/".to_string());
+
+        let bevy_origin = if cfg!(any(target_os = "windows", target_os = "android")) {
+            "http://bevy.ui"
+        } else {
+            "bevy://ui"
+        };
+        assert!(routes.is_allowed_origin(bevy_origin));
+        assert!(routes.is_allowed_origin("http://localhost:8080"));
+        assert!(!routes.is_allowed_origin("http://localhost:8081"));
+        assert!(!routes.is_allowed_origin("https://evil.example"));
+        assert!(!routes.is_allowed_origin("null"));
+        assert!(!routes.is_allowed_origin("bevy://"));
+    }
+}
@@ -0,0 +1,66 @@
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+use bevy::asset::io::{AssetReaderError, AssetSourceId};
+use bevy::prelude::*;
+use bevy::tasks::IoTaskPool;
+use wry::http::StatusCode;
+
+use super::{
+    content_response, mime_type, relative_path, status_response, ProtocolRequest, ASSETS_HOST,
+};
+
+/// Serve `request` from the asset source named by its host. Files are read on [IoTaskPool].
+pub(crate) fn serve_asset(request: ProtocolRequest, asset_server: Option<&AssetServer>) {
+    let Some(asset_server) = asset_server.cloned() else {
+        request.respond(status_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "AssetPlugin is not added",
+        ));
+        return;
+    };
+
+    let source = match request.host() {
+        ASSETS_HOST => AssetSourceId::Default,
+        name => AssetSourceId::Name(name.to_string().into()),
+    };
+    let Some(path) = relative_path(&request.path()).map(PathBuf::from) else {
+        request.respond(status_response(StatusCode::FORBIDDEN, "invalid path"));
+        return;
+    };
+
+    IoTaskPool::get()
+        .spawn(async move {
+            let response = match read_asset(&asset_server, source, &path).await {
+                Ok(bytes) => content_response(
+                    &request.request,
+                    mime_type(&path.to_string_lossy()),
+                    Cow::Owned(bytes),
+                ),
+                Err(AssetReaderError::NotFound(_)) => {
+                    status_response(StatusCode::NOT_FOUND, "not found")
+                }
+                Err(e) => status_response(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+            };
+            request.respond(response);
+        })
+        .detach();
+}
+
+async fn read_asset(
+    asset_server: &AssetServer,
+    source: AssetSourceId<'static>,
+    path: &Path,
+) -> Result<Vec<u8>, AssetReaderError> {
+    let source = asset_server
+        .get_source(source)
+        .map_err(|_| AssetReaderError::NotFound(path.to_path_buf()))?;
+    let mut reader = source.reader().read(path).await?;
+    let mut bytes = Vec::new();
+    reader
+        .read_to_end(&mut bytes)
+        .await
+        .map_err(|e| AssetReaderError::Io(e.into()))?;
+
+    Ok(bytes)
+}
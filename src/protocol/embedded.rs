@@ -0,0 +1,53 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use rust_embed::{EmbeddedFile, RustEmbed};
+use wry::http::StatusCode;
+
+use super::{content_response, mime_type, relative_path, status_response, ProtocolRequest};
+
+type GetFile = fn(&str) -> Option<EmbeddedFile>;
+
+/// [Resource] of UI bundles registered with [crate::register_ui_bundle], by name.
+#[derive(Resource, Default)]
+pub struct UiBundles {
+    bundles: HashMap<String, GetFile>,
+}
+
+impl UiBundles {
+    /// # Panics
+    /// Panics if another bundle is already registered as `name`.
+    pub(crate) fn insert<E: RustEmbed>(&mut self, name: String) {
+        if self.bundles.contains_key(&name) {
+            panic!("UI bundle '{name}' is already registered");
+        }
+
+        self.bundles.insert(name, E::get);
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.bundles.contains_key(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.bundles.keys().map(String::as_str)
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<GetFile> {
+        self.bundles.get(name).copied()
+    }
+}
+
+/// Serve `request` from UI bundle. Release builds serve files from memory, debug builds
+/// read them from disk, unless `rust-embed` `debug-embed` feature is enabled.
+pub(crate) fn serve_bundle(request: ProtocolRequest, get_file: GetFile) {
+    let Some(path) = relative_path(&request.path()) else {
+        request.respond(status_response(StatusCode::FORBIDDEN, "invalid path"));
+        return;
+    };
+
+    let response = match get_file(&path) {
+        Some(file) => content_response(&request.request, mime_type(&path), file.data),
+        None => status_response(StatusCode::NOT_FOUND, "not found"),
+    };
+    request.respond(response);
+}
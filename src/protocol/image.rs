@@ -0,0 +1,155 @@
+//! `bevy-image://image/<asset id>.png` - current contents of [Image] assets, encoded as PNG.
+//!
+//! Images rendered to on the GPU (with [TextureUsages::RENDER_ATTACHMENT]) are read back from
+//! the GPU on every request, so the page can show e.g. a minimap camera target.
+//! Add a query string to the url (`?frame=42`) to request fresh contents.
+use std::borrow::Cow;
+use std::io::Cursor;
+use std::marker::PhantomData;
+use std::sync::Mutex;
+
+use bevy::asset::AssetIndex;
+use bevy::prelude::*;
+use bevy::render::gpu_readback::{Readback, ReadbackComplete};
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureUsages};
+use bevy::tasks::AsyncComputeTaskPool;
+use wry::http::header::{CACHE_CONTROL, CONTENT_TYPE};
+use wry::http::{Response, StatusCode};
+
+use super::{protocol_url, status_response, ProtocolRequest};
+
+/// Scheme serving [Image] assets.
+pub const IMAGE_SCHEME: &str = "bevy-image";
+
+const IMAGE_HOST: &str = "image";
+
+/// Url of PNG encoded contents of image `id`, to be used in `<img src="...">`.
+pub fn image_url(id: impl Into<AssetId<Image>>) -> String {
+    let id = match id.into() {
+        AssetId::Index { index, .. } => format!("i{}", index.to_bits()),
+        AssetId::Uuid { uuid } => uuid.to_string(),
+    };
+    protocol_url(IMAGE_SCHEME, IMAGE_HOST, &format!("{id}.png"))
+}
+
+fn parse_image_id(path: &str) -> Option<AssetId<Image>> {
+    let id = path.strip_suffix(".png")?;
+    if let Some(bits) = id.strip_prefix('i') {
+        return Some(AssetId::Index {
+            index: AssetIndex::from_bits(bits.parse().ok()?),
+            marker: PhantomData,
+        });
+    }
+
+    Some(AssetId::Uuid {
+        uuid: id.parse().ok()?,
+    })
+}
+
+/// Serve `request` with PNG encoded image. Encoding runs on [AsyncComputeTaskPool].
+pub(crate) fn serve_image(
+    request: ProtocolRequest,
+    images: &Assets<Image>,
+    commands: &mut Commands,
+) {
+    let Some(id) = parse_image_id(&request.path()) else {
+        request.respond(status_response(StatusCode::NOT_FOUND, "invalid image id"));
+        return;
+    };
+
+    let Some(image) = images.get(id) else {
+        request.respond(status_response(StatusCode::NOT_FOUND, "image not found"));
+        return;
+    };
+
+    let is_render_target = image
+        .texture_descriptor
+        .usage
+        .contains(TextureUsages::RENDER_ATTACHMENT);
+    if !is_render_target {
+        encode_and_respond(request, image.clone());
+        return;
+    }
+
+    let Some(handle) = images.get_strong_handle(id) else {
+        request.respond(status_response(StatusCode::NOT_FOUND, "image not found"));
+        return;
+    };
+
+    // Observers can run multiple times, the request is answered with the first readback
+    let size = image.texture_descriptor.size;
+    let format = image.texture_descriptor.format;
+    let request = Mutex::new(Some(request));
+    commands.spawn(Readback::texture(handle)).observe(
+        move |trigger: Trigger<ReadbackComplete>, mut commands: Commands| {
+            commands.entity(trigger.entity()).despawn();
+            let Some(request) = request.lock().unwrap().take() else {
+                return;
+            };
+
+            let Some(data) = unpad_rows(&trigger.event().0, size, format.block_copy_size(None))
+            else {
+                request.respond(status_response(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "unsupported texture format",
+                ));
+                return;
+            };
+
+            let image = Image::new(
+                size,
+                TextureDimension::D2,
+                data,
+                format,
+                RenderAssetUsages::default(),
+            );
+            encode_and_respond(request, image);
+        },
+    );
+}
+
+/// GPU readback rows are aligned to 256 bytes.
+fn unpad_rows(data: &[u8], size: Extent3d, pixel_size: Option<u32>) -> Option<Vec<u8>> {
+    let row_size = (size.width * pixel_size?) as usize;
+    let rows = size.height as usize;
+    if data.len() == row_size * rows {
+        return Some(data.to_vec());
+    }
+
+    let padded_row_size = row_size.div_ceil(256) * 256;
+    let unpadded = data
+        .chunks(padded_row_size)
+        .take(rows)
+        .flat_map(|row| &row[..row_size.min(row.len())])
+        .copied()
+        .collect();
+    Some(unpadded)
+}
+
+fn encode_and_respond(request: ProtocolRequest, image: Image) {
+    AsyncComputeTaskPool::get()
+        .spawn(async move {
+            let response = match encode_png(image) {
+                Ok(png) => Response::builder()
+                    .header(CONTENT_TYPE, "image/png")
+                    .header(CACHE_CONTROL, "no-store")
+                    .body(Cow::Owned(png))
+                    .unwrap(),
+                Err(e) => status_response(StatusCode::INTERNAL_SERVER_ERROR, &e),
+            };
+            request.respond(response);
+        })
+        .detach();
+}
+
+fn encode_png(image: Image) -> Result<Vec<u8>, String> {
+    let image = image.try_into_dynamic().map_err(|e| e.to_string())?;
+    let mut png = Vec::new();
+    image
+        .to_rgba8()
+        .write_to(&mut Cursor::new(&mut png), ::image::ImageFormat::Png)
+        .map_err(|e| e.to_string())?;
+
+    Ok(png)
+}
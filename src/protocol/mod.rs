@@ -0,0 +1,343 @@
+//! Custom protocols serving content to webviews, without absolute paths or an HTTP server.
+//!
+//! `bevy://<host>/<path>` serves:
+//! - routes registered with [crate::register_route], if `host` is `api`
+//! - files of UI bundle embedded in the binary, if `host` is a name of bundle registered with
+//!   [crate::register_ui_bundle]
+//! - files from Bevy asset sources - `assets` is the default source, any other host is a named
+//!   source registered with [bevy::asset::AssetApp::register_asset_source]
+//!
+//! With `image` feature, `bevy-image://image/<asset id>.png` serves [bevy::prelude::Image] assets,
+//! see [image::image_url].
+//!
+//! On Windows and Android wry exposes custom protocols as `http://<scheme>.<host>/<path>`,
+//! use [protocol_url] (or [asset_url]) to get the url expected on the current platform.
+use std::borrow::Cow;
+use std::path::{Component, Path};
+use std::sync::{Arc, Mutex};
+
+use bevy::prelude::*;
+use wry::http::header::{ACCEPT_RANGES, CONTENT_RANGE, CONTENT_TYPE, RANGE};
+use wry::http::{Request, Response, StatusCode};
+use wry::{RequestAsyncResponder, WebViewId};
+
+pub mod api;
+pub mod assets;
+pub mod embedded;
+#[cfg(feature = "image")]
+pub mod image;
+
+/// Scheme serving Bevy assets.
+pub const BEVY_SCHEME: &str = "bevy";
+
+/// Host of the default asset source.
+pub const ASSETS_HOST: &str = "assets";
+
+/// Host of routes registered with [crate::register_route].
+pub const API_HOST: &str = "api";
+
+/// Url of `path` served by custom protocol `scheme` from `host`, in the form expected on
+/// the current platform.
+pub fn protocol_url(scheme: &str, host: &str, path: &str) -> String {
+    let path = path.trim_start_matches('/');
+    if cfg!(any(target_os = "windows", target_os = "android")) {
+        format!("http://{scheme}.{host}/{path}")
+    } else {
+        format!("{scheme}://{host}/{path}")
+    }
+}
+
+/// Url of `path` in the default asset source, e.g. `asset_url("ui/index.html")`.
+pub fn asset_url(path: &str) -> String {
+    protocol_url(BEVY_SCHEME, ASSETS_HOST, path)
+}
+
+/// Url of `path` in UI bundle registered as `bundle`, e.g. `bundle_url("ui", "index.html")`.
+pub fn bundle_url(bundle: &str, path: &str) -> String {
+    protocol_url(BEVY_SCHEME, bundle, path)
+}
+
+/// Url of route `path`, e.g. `fetch(api_url("/inventory"))`.
+pub fn api_url(path: &str) -> String {
+    protocol_url(BEVY_SCHEME, API_HOST, path)
+}
+
+/// Id of the native webview of `entity`, passed to custom protocol handlers.
+pub(crate) fn webview_id(entity: Entity) -> String {
+    entity.to_bits().to_string()
+}
+
+/// Custom protocol handler queueing requests to `scheme` in `requests`.
+///
+/// The webview is identified by its id, because on some platforms the handler registered
+/// by the first webview is shared by all of them. Requests with ids that aren't entities are
+/// answered with `400 Bad Request`.
+pub(crate) fn protocol_handler(
+    requests: ProtocolRequests,
+    scheme: &'static str,
+) -> impl Fn(WebViewId, Request<Vec<u8>>, RequestAsyncResponder) + 'static {
+    move |id, request, responder| {
+        let webview = id
+            .parse::<u64>()
+            .ok()
+            .and_then(|bits| Entity::try_from_bits(bits).ok());
+        let Some(webview) = webview else {
+            responder.respond(status_response(StatusCode::BAD_REQUEST, "unknown webview"));
+            return;
+        };
+
+        requests.push(ProtocolRequest {
+            webview,
+            scheme,
+            request,
+            responder,
+        });
+    }
+}
+
+/// Request made by the webview to one of bevy_wry custom protocols.
+pub(crate) struct ProtocolRequest {
+    pub webview: Entity,
+    pub scheme: &'static str,
+    pub request: Request<Vec<u8>>,
+    pub responder: RequestAsyncResponder,
+}
+
+impl ProtocolRequest {
+    /// Host of the request url, without `<scheme>.` prefix used on Windows and Android.
+    pub fn host(&self) -> &str {
+        let host = self.request.uri().host().unwrap_or_default();
+        host.strip_prefix(self.scheme)
+            .and_then(|host| host.strip_prefix('.'))
+            .unwrap_or(host)
+    }
+
+    /// Percent decoded path of the request url, without leading `/`.
+    pub fn path(&self) -> String {
+        percent_decode(self.request.uri().path().trim_start_matches('/'))
+    }
+
+    pub fn respond(self, response: Response<Cow<'static, [u8]>>) {
+        self.responder.respond(response);
+    }
+}
+
+/// Requests queued by wry custom protocol handlers, answered by
+/// [crate::systems::protocol] systems.
+#[derive(Resource, Clone, Default)]
+pub struct ProtocolRequests(Arc<Mutex<Vec<ProtocolRequest>>>);
+
+impl ProtocolRequests {
+    pub(crate) fn push(&self, request: ProtocolRequest) {
+        self.0.lock().unwrap().push(request);
+    }
+
+    /// Take queued requests matching `filter`, leaving other requests queued.
+    pub(crate) fn drain(&self, filter: impl Fn(&ProtocolRequest) -> bool) -> Vec<ProtocolRequest> {
+        let mut requests = self.0.lock().unwrap();
+        let (taken, rest) = requests.drain(..).partition(|request| filter(request));
+        *requests = rest;
+        taken
+    }
+}
+
+/// Response with `status` and plain text `message`.
+pub fn status_response(status: StatusCode, message: &str) -> Response<Cow<'static, [u8]>> {
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "text/plain")
+        .body(Cow::Owned(message.as_bytes().to_vec()))
+        .unwrap()
+}
+
+/// Response with `body`, honoring `Range` header of `request`.
+pub fn content_response(
+    request: &Request<Vec<u8>>,
+    content_type: &str,
+    body: Cow<'static, [u8]>,
+) -> Response<Cow<'static, [u8]>> {
+    let response = Response::builder()
+        .header(CONTENT_TYPE, content_type)
+        .header(ACCEPT_RANGES, "bytes");
+
+    let Some(range) = request.headers().get(RANGE) else {
+        return response.status(StatusCode::OK).body(body).unwrap();
+    };
+
+    let len = body.len();
+    let Some((start, end)) = range.to_str().ok().and_then(|range| byte_range(range, len)) else {
+        return response
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+            .header(CONTENT_RANGE, format!("bytes */{len}"))
+            .body(Cow::Borrowed(&[][..]))
+            .unwrap();
+    };
+
+    let body = match body {
+        Cow::Borrowed(body) => Cow::Borrowed(&body[start..=end]),
+        Cow::Owned(body) => Cow::Owned(body[start..=end].to_vec()),
+    };
+    response
+        .status(StatusCode::PARTIAL_CONTENT)
+        .header(CONTENT_RANGE, format!("bytes {start}-{end}/{len}"))
+        .body(body)
+        .unwrap()
+}
+
+/// Inclusive byte range of `Range: bytes=...` header. Only single ranges are supported.
+fn byte_range(header: &str, len: usize) -> Option<(usize, usize)> {
+    let range = header.trim().strip_prefix("bytes=")?;
+    if range.contains(',') || len == 0 {
+        return None;
+    }
+
+    let (start, end) = range.split_once('-')?;
+    let (start, end) = match (start.trim(), end.trim()) {
+        // Last `suffix` bytes
+        ("", suffix) => {
+            let suffix: usize = suffix.parse().ok()?;
+            (len.saturating_sub(suffix), len - 1)
+        }
+        (start, "") => (start.parse().ok()?, len - 1),
+        (start, end) => (start.parse().ok()?, end.parse::<usize>().ok()?.min(len - 1)),
+    };
+
+    (start <= end && start < len).then_some((start, end))
+}
+
+/// Relative path of the requested file. Directories are served with their `index.html`.
+/// Paths escaping the served directory are rejected, including Windows separators,
+/// drive letters and UNC prefixes.
+pub(crate) fn relative_path(path: &str) -> Option<String> {
+    let mut path = path.to_string();
+    if path.is_empty() || path.ends_with('/') {
+        path.push_str("index.html");
+    }
+
+    if path.contains(['\\', ':', '\0']) {
+        return None;
+    }
+
+    let valid_segments = path
+        .split('/')
+        .all(|segment| !matches!(segment, "" | "." | ".."));
+    let normal_components = Path::new(&path)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+    (valid_segments && normal_components).then_some(path)
+}
+
+/// MIME type of the file, by extension.
+pub fn mime_type(path: &str) -> &'static str {
+    let extension = path.rsplit_once('.').map(|(_, extension)| extension);
+    match extension.map(str::to_ascii_lowercase).as_deref() {
+        Some("html" | "htm") => "text/html",
+        Some("js" | "mjs") => "text/javascript",
+        Some("css") => "text/css",
+        Some("json" | "map") => "application/json",
+        Some("wasm") => "application/wasm",
+        Some("txt") => "text/plain",
+        Some("xml") => "application/xml",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("avif") => "image/avif",
+        Some("ico") => "image/x-icon",
+        Some("woff") => "font/woff",
+        Some("woff2") => "font/woff2",
+        Some("ttf") => "font/ttf",
+        Some("otf") => "font/otf",
+        Some("mp3") => "audio/mpeg",
+        Some("ogg") => "audio/ogg",
+        Some("wav") => "audio/wav",
+        Some("mp4") => "video/mp4",
+        Some("webm") => "video/webm",
+        _ => "application/octet-stream",
+    }
+}
+
+fn percent_decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_path_serves_index_of_directories() {
+        assert_eq!(relative_path("").as_deref(), Some("index.html"));
+        assert_eq!(relative_path("ui/").as_deref(), Some("ui/index.html"));
+        assert_eq!(relative_path("ui/main.js").as_deref(), Some("ui/main.js"));
+    }
+
+    #[test]
+    fn relative_path_rejects_traversal() {
+        for path in [
+            "..",
+            "../secret",
+            "ui/../../secret",
+            "./index.html",
+            "ui//index.html",
+            "/etc/passwd",
+            "..\\..\\secret",
+            "ui\\..\\secret",
+            "C:/Windows/win.ini",
+            "C:secret",
+            "\\\\server\\share\\file",
+            "ui/file\0.html",
+        ] {
+            assert_eq!(relative_path(path), None, "{path}");
+        }
+    }
+
+    #[test]
+    fn byte_range_parses_single_ranges() {
+        assert_eq!(byte_range("bytes=0-9", 100), Some((0, 9)));
+        assert_eq!(byte_range("bytes=10-", 100), Some((10, 99)));
+        assert_eq!(byte_range("bytes=-10", 100), Some((90, 99)));
+        assert_eq!(byte_range("bytes=-200", 100), Some((0, 99)));
+        assert_eq!(byte_range("bytes=90-200", 100), Some((90, 99)));
+    }
+
+    #[test]
+    fn byte_range_rejects_unsatisfiable_ranges() {
+        assert_eq!(byte_range("bytes=100-", 100), None);
+        assert_eq!(byte_range("bytes=10-5", 100), None);
+        assert_eq!(byte_range("bytes=0-9", 0), None);
+        assert_eq!(byte_range("bytes=0-1,5-6", 100), None);
+        assert_eq!(byte_range("items=0-9", 100), None);
+        assert_eq!(byte_range("bytes=a-b", 100), None);
+    }
+
+    #[test]
+    fn percent_decode_decodes_escapes() {
+        assert_eq!(percent_decode("my%20file.html"), "my file.html");
+        assert_eq!(percent_decode("%C5%BC%C3%B3%C5%82w"), "żółw");
+        assert_eq!(percent_decode("%2e%2E"), "..");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz%4"), "%zz%4");
+    }
+}
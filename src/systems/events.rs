@@ -1,50 +1,366 @@
 use bevy::prelude::*;
 
-use crate::components::webview::{WebViewComponent, WebViews};
-use crate::events::{InMessageBus, InWryEvent, OutMessageBus, OutWryEvent};
+use crate::components::webview::{Ready, WebViewComponent, WebViews};
+use crate::events::error::{Error, WryIpcError};
+use crate::events::lifecycle::{is_page_tag, NativeEvent, WebViewJsError};
+use crate::events::query::{JsQueries, JsQueryError, JsQueryOutcome, JsQueryResponse};
+use crate::events::request::{InWryRequest, Responder, WryRequest};
+use crate::events::{
+    InMessageBus, InWryEvent, IncomingEvents, IpcEnvelope, MessageBus, NativeEventBus,
+    OutMessageBus, OutWryEvent,
+};
 
-/// Consume all messages from [InMessageBus] and trigger corresponding events.
+/// Consume [NativeEventBus] and [InMessageBus] of every webview and trigger corresponding events.
 ///
-/// Messages stored in [InMessageBus] are received via webview IPC mechanism.
-/// Events triggered this way can be received via observer pattern.
-pub(crate) fn consume_ipfs_events<E>(
+/// Native notifications and messages posted by the page are triggered in the order they were
+/// received, followed by messages queued in [InMessageBus].
+/// Each message is parsed once as [IpcEnvelope] and only the event registered
+/// under its tag is triggered. Events triggered this way can be received via observer pattern.
+/// Tags reserved for bevy_wry notifications are rejected, see
+/// [crate::events::lifecycle::RESERVED_TAG_PREFIX].
+/// Requests that cannot be handled are rejected right away.
+pub(crate) fn consume_ipc_messages(
     mut commands: Commands,
-    webviews: Query<(Entity, &InMessageBus)>,
-) where
+    incoming_events: Res<IncomingEvents>,
+    webviews: Query<(Entity, &NativeEventBus, &InMessageBus, &OutMessageBus)>,
+) {
+    for (entity, native_bus, in_bus, out_bus) in webviews.iter() {
+        for event in native_bus.drain() {
+            match event {
+                NativeEvent::Ipc(msg) => {
+                    handle_ipc_message(&mut commands, &incoming_events, entity, out_bus, msg)
+                }
+                NativeEvent::PageLoad(page_load) => commands.trigger_targets(page_load, entity),
+                NativeEvent::Navigation(navigation) => commands.trigger_targets(navigation, entity),
+                NativeEvent::NavigationBlocked(blocked) => {
+                    commands.trigger_targets(blocked, entity)
+                }
+                NativeEvent::NewWindow(request) => commands.trigger_targets(request, entity),
+            }
+        }
+
+        let messages: Vec<String> = in_bus.write().drain(..).collect();
+        for msg in messages {
+            handle_ipc_message(&mut commands, &incoming_events, entity, out_bus, msg);
+        }
+    }
+}
+
+fn handle_ipc_message(
+    commands: &mut Commands,
+    incoming_events: &IncomingEvents,
+    entity: Entity,
+    out_bus: &OutMessageBus,
+    msg: String,
+) {
+    let envelope: IpcEnvelope = match serde_json::from_str(&msg) {
+        Ok(envelope) => envelope,
+        Err(e) => {
+            warn!("Failed to parse ipc message '{msg}': {e}");
+            report_ipc_error(commands, entity, msg, Error::Deserialize);
+            return;
+        }
+    };
+
+    let IpcEnvelope { tag, id, payload } = envelope;
+    if !is_page_tag(&tag) {
+        warn!("Received ipc message with reserved type '{tag}'");
+        if let Some(id) = id {
+            reject_request(id, out_bus, format!("reserved message type '{tag}'"));
+        }
+        report_ipc_error(commands, entity, msg, Error::BadMessageType);
+        return;
+    }
+
+    let Some(trigger) = incoming_events.get(&tag) else {
+        warn!("Received ipc message with unknown type '{tag}'");
+        if let Some(id) = id {
+            reject_request(id, out_bus, format!("unknown message type '{tag}'"));
+        }
+        report_ipc_error(commands, entity, msg, Error::BadMessageType);
+        return;
+    };
+
+    if let Err(e) = trigger(commands, entity, out_bus, id, payload) {
+        warn!("Failed to deserialize '{tag}' payload: {e}");
+        if let Some(id) = id {
+            reject_request(id, out_bus, e.to_string());
+        }
+        report_ipc_error(commands, entity, msg, Error::Deserialize);
+    }
+}
+
+/// Deserialize `payload` into `E` and trigger it on the webview `entity`.
+///
+/// Event sent with `bevy.invoke` is acknowledged by resolving its `Promise` with `null`.
+pub(crate) fn trigger_incoming_event<E>(
+    commands: &mut Commands,
+    entity: Entity,
+    out_bus: &OutMessageBus,
+    id: Option<u64>,
+    payload: serde_json::Value,
+) -> Result<(), serde_json::Error>
+where
     for<'de> E: InWryEvent<'de>,
 {
-    for (entity, msg_bus) in webviews.iter() {
-        for msg in msg_bus.read().iter() {
-            let event: E = serde_json::from_str(msg).unwrap();
-            commands.trigger_targets(event, entity);
-        }
+    let event: E = serde_json::from_value(payload)?;
+    commands.trigger_targets(event, entity);
+    if let Some(id) = id {
+        Responder::<()>::new(id, MessageBus::clone(out_bus)).respond(());
     }
+
+    Ok(())
+}
+
+/// Deserialize `payload` into `R` and trigger [WryRequest] on the webview `entity`.
+///
+/// Request sent without `id` can't be answered, so it is rejected as bad message.
+pub(crate) fn trigger_incoming_request<R: InWryRequest>(
+    commands: &mut Commands,
+    entity: Entity,
+    out_bus: &OutMessageBus,
+    id: Option<u64>,
+    payload: serde_json::Value,
+) -> Result<(), serde_json::Error> {
+    let Some(id) = id else {
+        warn!("Request was sent without id, use `bevy.invoke` to send requests");
+        let message = serde_json::json!({ "payload": payload }).to_string();
+        report_ipc_error(commands, entity, message, Error::BadMessageType);
+        return Ok(());
+    };
+
+    let request: R = serde_json::from_value(payload)?;
+    let responder = Responder::new(id, MessageBus::clone(out_bus));
+    commands.trigger_targets(WryRequest { request, responder }, entity);
+    Ok(())
+}
+
+fn reject_request(id: u64, out_bus: &OutMessageBus, message: String) {
+    Responder::<()>::new(id, MessageBus::clone(out_bus)).reject(message);
+}
+
+/// Resolve pending [crate::events::query::JsQuery] answered by the page.
+pub(crate) fn resolve_js_queries(
+    trigger: Trigger<JsQueryResponse>,
+    mut commands: Commands,
+    mut queries: ResMut<JsQueries>,
+) {
+    let JsQueryResponse { id, result } = trigger.event();
+    let Some(query) = queries.pending.get(id) else {
+        warn!("Received answer to unknown or expired query {id}");
+        return;
+    };
+
+    // Query ids are predictable, so pages could answer queries sent to other webviews
+    if trigger.entity() != query.webview {
+        warn!(
+            "Webview {} answered query {id} sent to webview {}",
+            trigger.entity(),
+            query.webview
+        );
+        return;
+    }
+
+    let query = queries.pending.remove(id).unwrap();
+
+    let result = match result {
+        JsQueryOutcome::Ok(value) => Ok(value.clone()),
+        JsQueryOutcome::Err(message) => Err(JsQueryError::Script(message.clone())),
+    };
+    (query.resolve)(&mut commands, query.webview, *id, result);
+}
+
+/// Fail [crate::events::query::JsQuery]s the page didn't answer before their deadline.
+pub(crate) fn expire_js_queries(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut queries: ResMut<JsQueries>,
+) {
+    let now = time.elapsed();
+    queries.pending.retain(|id, query| {
+        if query.deadline > now {
+            return true;
+        }
+
+        (query.resolve)(
+            &mut commands,
+            query.webview,
+            *id,
+            Err(JsQueryError::Timeout),
+        );
+        false
+    });
+}
+
+pub(crate) fn log_js_errors(trigger: Trigger<WebViewJsError>) {
+    let WebViewJsError {
+        message,
+        source,
+        line,
+    } = trigger.event();
+    let location = match (source, line) {
+        (Some(source), Some(line)) => format!(" ({source}:{line})"),
+        (Some(source), None) => format!(" ({source})"),
+        _ => String::new(),
+    };
+    warn!("WebView {} error: {message}{location}", trigger.entity());
+}
+
+/// Trigger [WryIpcError] on the webview `entity`.
+pub(crate) fn report_ipc_error(
+    commands: &mut Commands,
+    entity: Entity,
+    message: String,
+    error: Error,
+) {
+    commands.trigger_targets(
+        WryIpcError {
+            webview: entity,
+            message,
+            error,
+        },
+        entity,
+    );
 }
 
 /// Consume incoming [OutWryEvent] and push it's script representation to [OutMessageBus].
 pub(crate) fn produce_out_scripts<E: OutWryEvent>(
     trigger: Trigger<E>,
+    mut commands: Commands,
     out_bus: Query<&OutMessageBus>,
 ) {
-    let ob = out_bus.get(trigger.entity()).unwrap();
+    let entity = trigger.entity();
     let event: &E = trigger.event();
+    let Ok(ob) = out_bus.get(entity) else {
+        warn!("Cannot send event to {entity}: entity is not a webview");
+        report_ipc_error(
+            &mut commands,
+            entity,
+            event.to_script(),
+            Error::WebViewNotInitialized,
+        );
+        return;
+    };
+
     ob.write().push(event.to_script());
 }
 
+/// Evaluate scripts queued in [OutMessageBus] of [Ready] webviews.
+/// Scripts sent to webviews that are not ready yet stay queued.
 pub(crate) fn clear_busses(
+    mut commands: Commands,
     webviews: NonSend<WebViews>,
-    busses: Query<(&WebViewComponent, &InMessageBus, &OutMessageBus)>,
+    busses: Query<(Entity, &OutMessageBus), (With<WebViewComponent>, With<Ready>)>,
 ) {
-    for (webview_component, in_bus, out_bus) in busses.iter() {
-        for msg in out_bus.read().iter() {
-            webviews
-                .get_webview(&webview_component.webview_name)
-                .unwrap()
-                .evaluate_script(msg)
-                .unwrap();
+    for (entity, out_bus) in busses.iter() {
+        let scripts: Vec<String> = out_bus.write().drain(..).collect();
+        let Some(webview) = webviews.get_webview(entity) else {
+            warn!("WebView of entity {entity} doesn't exist");
+            for script in scripts {
+                report_ipc_error(&mut commands, entity, script, Error::WebViewNotInitialized);
+            }
+            continue;
+        };
+
+        for script in scripts {
+            if let Err(e) = webview.evaluate_script(&script) {
+                warn!("Failed to evaluate script '{script}': {e}");
+                report_ipc_error(&mut commands, entity, script, Error::EvaluateScript);
+            }
         }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Event, Deserialize, Debug, PartialEq)]
+    struct Rotate {
+        angle: f32,
+    }
+
+    #[derive(Event, Deserialize, Debug, PartialEq)]
+    struct Exit;
+
+    /// Events and errors triggered on the webview, in order.
+    #[derive(Resource, Default)]
+    struct Received(Vec<String>);
+
+    fn world() -> (World, Entity) {
+        let mut world = World::new();
+        let mut incoming_events = IncomingEvents::default();
+        incoming_events.insert("Rotate".to_string(), trigger_incoming_event::<Rotate>);
+        incoming_events.insert("Exit".to_string(), trigger_incoming_event::<Exit>);
+        world.insert_resource(incoming_events);
+        world.init_resource::<Received>();
+        world.add_observer(|trigger: Trigger<Rotate>, mut received: ResMut<Received>| {
+            received.0.push(format!("Rotate {}", trigger.event().angle));
+        });
+        world.add_observer(|_: Trigger<Exit>, mut received: ResMut<Received>| {
+            received.0.push("Exit".to_string());
+        });
+        world.add_observer(
+            |trigger: Trigger<WryIpcError>, mut received: ResMut<Received>| {
+                received.0.push(format!("{:?}", trigger.event().error));
+            },
+        );
+
+        let webview = world
+            .spawn((
+                NativeEventBus::default(),
+                InMessageBus::default(),
+                OutMessageBus::default(),
+            ))
+            .id();
+        (world, webview)
+    }
+
+    fn consume(messages: &[&str]) -> Vec<String> {
+        let (mut world, webview) = world();
+        world
+            .get::<InMessageBus>(webview)
+            .unwrap()
+            .write()
+            .extend(messages.iter().map(|message| message.to_string()));
+        world.run_system_once(consume_ipc_messages).unwrap();
+        world.flush();
+        std::mem::take(&mut world.resource_mut::<Received>().0)
+    }
+
+    #[test]
+    fn only_event_registered_under_tag_is_triggered() {
+        let received = consume(&[
+            r#"{"type": "Rotate", "payload": {"angle": 30}}"#,
+            r#"{"type": "Exit", "payload": null}"#,
+        ]);
+        assert_eq!(received, ["Rotate 30", "Exit"]);
+    }
+
+    #[test]
+    fn unknown_tag_is_bad_message_type() {
+        let received = consume(&[
+            r#"{"type": "Jump", "payload": {}}"#,
+            r#"{"type": "__bevy_page_load", "payload": {}}"#,
+        ]);
+        assert_eq!(received, ["BadMessageType", "BadMessageType"]);
+    }
+
+    #[test]
+    fn bad_payload_is_deserialize_error() {
+        let received = consume(&[
+            r#"{"type": "Rotate", "payload": {"angle": "left"}}"#,
+            r#"{"type": "Rotate""#,
+        ]);
+        assert_eq!(received, ["Deserialize", "Deserialize"]);
+    }
 
-        in_bus.clear();
-        out_bus.clear();
+    #[test]
+    fn missing_payload_is_unit() {
+        let received = consume(&[r#"{"type": "Exit"}"#]);
+        assert_eq!(received, ["Exit"]);
     }
 }
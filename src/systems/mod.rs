@@ -1,8 +1,137 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
 use bevy::prelude::*;
+use bevy::render::view::ExtractedWindows;
+use bevy::window::PrimaryWindow;
+use bevy::winit::WinitWindows;
 
 pub mod events;
+pub mod protocol;
 pub mod webview;
 
-pub fn boot_delay_elapsed(time: Res<Time>) -> bool {
-    time.elapsed_secs() > 1.0
+/// Sets of bevy_wry systems, for ordering game systems against them.
+///
+/// [WrySet::ReceiveIpc], [WrySet::Create] and [WrySet::Layout] run in [Update] and
+/// [WrySet::FlushIpc] in [PostUpdate], unless configured otherwise with
+/// [crate::BevyWryPlugin::with_schedule] and [crate::BevyWryPlugin::with_flush_schedule].
+///
+/// Example
+/// ```rust
+/// use bevy::prelude::*;
+/// use bevy_wry::systems::WrySet;
+///
+/// fn gameplay() {}
+///
+/// let mut app = App::new();
+/// // Handle UI commands received this frame
+/// app.add_systems(Update, gameplay.after(WrySet::ReceiveIpc));
+/// ```
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum WrySet {
+    /// Trigger messages received from webviews
+    ReceiveIpc,
+    /// Create native webviews and load changed sources
+    Create,
+    /// Update webview bounds, after [WrySet::Create]
+    Layout,
+    /// Evaluate scripts queued for webviews
+    FlushIpc,
+}
+
+/// Startup of bevy_wry. Webviews are created and laid out only in [WryStartupState::Ready],
+/// so systems spawning webviews don't have to wait for the window.
+///
+/// Use `OnEnter(WryStartupState::Ready)` to run systems once webviews can be created.
+#[derive(States, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum WryStartupState {
+    /// Waiting for the primary window and its surface
+    #[default]
+    WaitingForWindow,
+    Ready,
+}
+
+/// Additional delay between the first presented frame of the primary window and
+/// [WryStartupState::Ready], set with [crate::BevyWryPlugin::with_startup_delay].
+#[derive(Resource, Debug, Clone, Copy, Default)]
+pub struct WryStartupDelay(pub Duration);
+
+/// Time after app start to enter [WryStartupState::Ready] even if no frame of the primary
+/// window was presented (e.g. without `RenderPlugin` or without primary window), set with
+/// [crate::BevyWryPlugin::with_startup_timeout]. Default: 1 second
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct WryStartupTimeout(pub Duration);
+
+impl Default for WryStartupTimeout {
+    fn default() -> Self {
+        Self(Duration::from_secs(1))
+    }
+}
+
+/// Set by the render world once a frame of the primary window was presented.
+#[derive(Resource, Clone, Default)]
+pub(crate) struct FramePresented(Arc<AtomicBool>);
+
+impl FramePresented {
+    fn get(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
+
+/// Render world system setting [FramePresented].
+///
+/// Surface textures are presented at the end of `RenderSet::Render`, so a texture acquired
+/// for the primary window in the previous frame has been presented.
+pub(crate) fn detect_present(
+    windows: Res<ExtractedWindows>,
+    presented: Res<FramePresented>,
+    mut acquired: Local<bool>,
+) {
+    if *acquired {
+        presented.0.store(true, Ordering::Release);
+        return;
+    }
+
+    *acquired = windows
+        .primary
+        .and_then(|primary| windows.get(&primary))
+        .is_some_and(|window| window.swap_chain_texture.is_some());
+}
+
+/// Enter [WryStartupState::Ready] once a frame of the primary window was presented.
+///
+/// Since wgpu 23 (bevy commit 4b05d2f4, wgpu commit fb0cb1eb) rendering doesn't work when
+/// the webview is created before the window surface presents its first frame. If no frame
+/// is presented within [WryStartupTimeout], webviews are created anyway - without primary
+/// window they fail with [crate::Error::FailedToGetMainWindow].
+pub(crate) fn detect_startup(
+    primary_window: Query<Entity, With<PrimaryWindow>>,
+    winit_windows: Option<NonSend<WinitWindows>>,
+    presented: Res<FramePresented>,
+    delay: Res<WryStartupDelay>,
+    timeout: Res<WryStartupTimeout>,
+    time: Res<Time<Real>>,
+    mut started_at: Local<Option<Duration>>,
+    mut presented_at: Local<Option<Duration>>,
+    mut next_state: ResMut<NextState<WryStartupState>>,
+) {
+    let now = time.elapsed();
+    let started_at = *started_at.get_or_insert(now);
+    let has_window = primary_window.get_single().is_ok_and(|primary_window| {
+        winit_windows.is_some_and(|windows| windows.get_window(primary_window).is_some())
+    });
+
+    if has_window && presented.get() {
+        let presented_at = *presented_at.get_or_insert(now);
+        if now - presented_at >= delay.0 {
+            next_state.set(WryStartupState::Ready);
+        }
+    } else if now - started_at >= timeout.0 {
+        warn!(
+            "No frame of the primary window was presented in {:?}, creating webviews anyway",
+            timeout.0
+        );
+        next_state.set(WryStartupState::Ready);
+    }
 }
@@ -0,0 +1,115 @@
+use bevy::prelude::*;
+use wry::http::header::ORIGIN;
+use wry::http::{Method, StatusCode};
+
+use crate::components::webview::WebViews;
+use crate::protocol::api::{
+    api_request, api_response, preflight_response, ApiResponse, ApiRoutes, RouteMatch,
+};
+use crate::protocol::assets::serve_asset;
+use crate::protocol::embedded::{serve_bundle, UiBundles};
+#[cfg(feature = "image")]
+use crate::protocol::image::{serve_image, IMAGE_SCHEME};
+#[cfg(feature = "image")]
+use crate::protocol::status_response;
+use crate::protocol::{ProtocolRequest, ProtocolRequests, API_HOST, BEVY_SCHEME};
+
+fn is_api_request(request: &ProtocolRequest) -> bool {
+    request.scheme == BEVY_SCHEME && request.host() == API_HOST
+}
+
+/// Answer requests queued by `bevy://` protocol handler, except `bevy://api` routes.
+pub(crate) fn serve_protocol_requests(
+    requests: Res<ProtocolRequests>,
+    bundles: Res<UiBundles>,
+    asset_server: Option<Res<AssetServer>>,
+) {
+    let requests =
+        requests.drain(|request| request.scheme == BEVY_SCHEME && !is_api_request(request));
+    for request in requests {
+        match bundles.get(request.host()) {
+            Some(get_file) => serve_bundle(request, get_file),
+            None => serve_asset(request, asset_server.as_deref()),
+        }
+    }
+}
+
+/// Answer `bevy://api` requests with route handlers registered with [crate::register_route].
+///
+/// Requests of webviews that don't exist anymore are rejected with `400 Bad Request`.
+/// Requests without `Origin` header (e.g. `<img src="bevy://api/...">` or navigation) and
+/// from origins not allowed by [ApiRoutes] are rejected with `403 Forbidden`, before
+/// reaching the handler.
+pub(crate) fn serve_api_requests(world: &mut World) {
+    let requests = world.resource::<ProtocolRequests>().drain(is_api_request);
+    for request in requests {
+        let is_webview = world
+            .get_non_send_resource::<WebViews>()
+            .is_some_and(|webviews| webviews.get_webview(request.webview).is_some());
+        if !is_webview {
+            let response = ApiResponse::status(StatusCode::BAD_REQUEST, "unknown webview");
+            request.respond(api_response(response, None));
+            continue;
+        }
+
+        let origin = request
+            .request
+            .headers()
+            .get(ORIGIN)
+            .and_then(|origin| origin.to_str().ok())
+            .map(str::to_string);
+        let routes = world.resource::<ApiRoutes>();
+        let Some(origin) = origin.filter(|origin| routes.is_allowed_origin(origin)) else {
+            warn!(
+                "Rejected api request {} without allowed origin",
+                request.request.uri()
+            );
+            let response = ApiResponse::status(StatusCode::FORBIDDEN, "origin not allowed");
+            request.respond(api_response(response, None));
+            continue;
+        };
+
+        let origin = Some(origin.as_str());
+        if request.request.method() == Method::OPTIONS {
+            let methods = routes.methods(request.request.uri().path());
+            request.respond(preflight_response(origin, &methods));
+            continue;
+        }
+
+        let mut api_request = api_request(&request);
+        let route = routes.find(&api_request.method, &api_request.path);
+        let response = match route {
+            RouteMatch::Found(handler, params) => {
+                api_request.params = params;
+                world
+                    .run_system_with_input(handler, api_request)
+                    .unwrap_or_else(|e| {
+                        ApiResponse::status(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+                    })
+            }
+            RouteMatch::MethodNotAllowed => {
+                ApiResponse::status(StatusCode::METHOD_NOT_ALLOWED, "method not allowed")
+            }
+            RouteMatch::NotFound => ApiResponse::status(StatusCode::NOT_FOUND, "no such route"),
+        };
+        request.respond(api_response(response, origin));
+    }
+}
+
+/// Answer requests queued by `bevy-image://` protocol handler.
+#[cfg(feature = "image")]
+pub(crate) fn serve_image_requests(
+    requests: Res<ProtocolRequests>,
+    images: Option<Res<Assets<Image>>>,
+    mut commands: Commands,
+) {
+    for request in requests.drain(|request| request.scheme == IMAGE_SCHEME) {
+        match images.as_deref() {
+            Some(images) => serve_image(request, images, &mut commands),
+            None => request.respond(status_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "ImagePlugin is not added",
+            )),
+        }
+    }
+}
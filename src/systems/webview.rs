@@ -2,13 +2,42 @@ use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 use bevy::winit::WinitWindows;
 use std::ops::Deref;
+use std::sync::Arc;
 use wry::WebViewBuilder;
 
+use crate::client::{CLIENT_SCRIPT, CLIENT_VERSION, TAURI_SHIM_SCRIPT};
 use crate::components::bounds::{to_webview_bounds, Position, Size};
-use crate::components::webview::{Initialized, Source, Transparency, WebViewComponent, WebViews};
+use crate::components::navigation::{open_external, NavigationPolicy, SharedNavigationPolicy};
+use crate::components::webview::{
+    Initialized, Ready, ReadySignal, Source, Transparency, WebViewComponent, WebViewFailed,
+    WebViewLoadState, WebViews,
+};
 use crate::components::Anchor;
-use crate::events::{InMessageBus, OutMessageBus};
+use crate::error::Error;
+use crate::events::lifecycle::{
+    NativeEvent, NavigationBlocked, NavigationRequested, NewWindowRequested, PageLoad,
+    PageLoadFinished, PageLoadStarted, ReadyHandshake, WebViewReady,
+};
+use crate::events::NativeEventBus;
+#[cfg(feature = "image")]
+use crate::protocol::image::IMAGE_SCHEME;
+use crate::protocol::{
+    asset_url, bundle_url, protocol_handler, webview_id, ProtocolRequests, BEVY_SCHEME,
+};
 
+/// Enable devtools of created webviews, set with [crate::BevyWryPlugin::with_devtools].
+#[derive(Resource, Debug, Clone, Copy, Default)]
+pub struct WryDevtools(pub bool);
+
+/// Inject [TAURI_SHIM_SCRIPT] into created webviews, set with
+/// [crate::BevyWryPlugin::with_tauri_shim].
+#[derive(Resource, Debug, Clone, Copy, Default)]
+pub struct WryTauriShim(pub bool);
+
+/// Create native webviews of new [WebViewComponent] entities.
+///
+/// Failures are logged, inserted as [WebViewFailed] component and triggered on the entity.
+/// Remove [WebViewFailed] to retry.
 #[allow(clippy::type_complexity)]
 pub fn create_webviews(
     mut commands: Commands,
@@ -16,53 +45,225 @@ pub fn create_webviews(
     webview_entities: Query<
         (
             Entity,
-            &WebViewComponent,
             &Anchor,
             &Position,
             &Size,
             &Source,
             &Transparency,
+            &NativeEventBus,
+            &SharedNavigationPolicy,
+        ),
+        (
+            With<WebViewComponent>,
+            Without<Initialized>,
+            Without<WebViewFailed>,
         ),
-        Without<Initialized>,
     >,
     primary_window_entity: Query<Entity, With<PrimaryWindow>>,
     winit_windows: NonSend<WinitWindows>,
+    devtools: Res<WryDevtools>,
+    tauri_shim: Res<WryTauriShim>,
+    protocol_requests: Res<ProtocolRequests>,
 ) {
-    let primary_window = primary_window_entity.single();
-    let primary_window = winit_windows.get_window(primary_window).unwrap();
+    if webview_entities.is_empty() {
+        return;
+    }
+
+    let Ok(primary_window) = primary_window_entity.get_single() else {
+        for (entity, ..) in webview_entities.iter() {
+            fail_webview(&mut commands, entity, Error::FailedToGetMainWindow);
+        }
+        return;
+    };
+
+    // Native window is created asynchronously, wait for it
+    let Some(primary_window) = winit_windows.get_window(primary_window) else {
+        return;
+    };
+
     let window_size = primary_window.inner_size();
     let scale_factor = primary_window.scale_factor();
-
-    for (entity, webview_component, anchor, position, size, source, transparency) in
+    for (entity, anchor, position, size, source, transparency, native_bus, navigation_policy) in
         webview_entities.iter()
     {
         let bounds = to_webview_bounds(*anchor, position.0, size.0, window_size, scale_factor);
+        let id = webview_id(entity);
         let builder = WebViewBuilder::new()
+            .with_id(&id)
             .with_transparent(transparency.0)
-            .with_bounds(bounds);
+            .with_devtools(devtools.0)
+            .with_bounds(bounds)
+            .with_initialization_script(CLIENT_SCRIPT)
+            .with_asynchronous_custom_protocol(
+                BEVY_SCHEME.to_string(),
+                protocol_handler(ProtocolRequests::clone(&protocol_requests), BEVY_SCHEME),
+            );
+        #[cfg(feature = "image")]
+        let builder = builder.with_asynchronous_custom_protocol(
+            IMAGE_SCHEME.to_string(),
+            protocol_handler(ProtocolRequests::clone(&protocol_requests), IMAGE_SCHEME),
+        );
+        let builder = if tauri_shim.0 {
+            builder.with_initialization_script(TAURI_SHIM_SCRIPT)
+        } else {
+            builder
+        };
 
         let builder = match source {
             Source::Url(url) => builder.with_url(url.clone()),
             Source::Html(html) => builder.with_html(html.clone()),
+            Source::Asset(path) => builder.with_url(asset_url(path)),
+            Source::Bundle { bundle, path } => builder.with_url(bundle_url(bundle, path)),
         };
 
-        let in_bus = InMessageBus::default();
-        let ipc_bus = in_bus.clone();
+        let ipc_bus = native_bus.clone();
+        let page_load_bus = native_bus.clone();
+        let navigation_bus = native_bus.clone();
+        let navigation_policy = navigation_policy.clone();
+        let new_window_bus = native_bus.clone();
         let webview = builder
             .with_ipc_handler(move |request| {
-                ipc_bus.write().push(request.body().clone());
+                ipc_bus.push(NativeEvent::Ipc(request.body().clone()));
+            })
+            .with_on_page_load_handler(move |event, url| {
+                page_load_bus.push(NativeEvent::PageLoad(PageLoad::new(event, url)));
+            })
+            .with_navigation_handler(move |url| {
+                if navigation_policy.is_allowed(&url) {
+                    navigation_bus.push(NativeEvent::Navigation(NavigationRequested { url }));
+                    return true;
+                }
+
+                let opened_externally = navigation_policy.open_external() && open_external(&url);
+                navigation_bus.push(NativeEvent::NavigationBlocked(NavigationBlocked {
+                    url,
+                    opened_externally,
+                }));
+                false
+            })
+            .with_new_window_req_handler(move |url| {
+                // Native popups are never opened, observers of NewWindowRequested decide
+                new_window_bus.push(NativeEvent::NewWindow(NewWindowRequested { url }));
+                false
             })
-            .build_as_child(primary_window.deref())
-            .unwrap();
+            .build_as_child(primary_window.deref());
+
+        match webview {
+            Ok(webview) => {
+                webviews.insert(entity, webview);
+                commands.entity(entity).insert(Initialized);
+            }
+            Err(e) => fail_webview(&mut commands, entity, e.into()),
+        }
+    }
+}
+
+fn fail_webview(commands: &mut Commands, entity: Entity, error: Error) {
+    error!("Failed to create webview {entity}: {error}");
+    let failed = WebViewFailed(Arc::new(error));
+    commands.entity(entity).insert(failed.clone());
+    commands.trigger_targets(failed, entity);
+}
+
+/// Mark webviews using [ReadySignal::Handshake] as [Ready] when the page calls `bevy.ready()`.
+pub(crate) fn handle_ready_handshake(
+    trigger: Trigger<ReadyHandshake>,
+    mut commands: Commands,
+    webviews: Query<(&ReadySignal, Has<Ready>)>,
+) {
+    let entity = trigger.entity();
+    let version = &trigger.event().version;
+    if version != CLIENT_VERSION {
+        warn!("WebView {entity} uses bevy_wry client {version}, expected {CLIENT_VERSION}");
+    }
+
+    let Ok((ready_signal, is_ready)) = webviews.get(entity) else {
+        return;
+    };
 
-        let WebViewComponent { webview_name } = webview_component;
-        webviews.insert(webview_name.clone(), webview);
+    if *ready_signal == ReadySignal::Handshake && !is_ready {
+        commands.entity(entity).insert(Ready);
+        commands.trigger_targets(
+            WebViewReady {
+                version: Some(version.clone()),
+            },
+            entity,
+        );
+    }
+}
+
+/// Update [WebViewLoadState], trigger [PageLoadStarted] and [PageLoadFinished].
+/// Reset [Ready] when the page starts loading and mark webviews using
+/// [ReadySignal::PageLoad] as ready when it finishes.
+pub(crate) fn handle_page_load(
+    trigger: Trigger<PageLoad>,
+    mut commands: Commands,
+    mut webviews: Query<(&mut WebViewLoadState, &ReadySignal, Has<Ready>)>,
+) {
+    let entity = trigger.entity();
+    let Ok((mut load_state, ready_signal, is_ready)) = webviews.get_mut(entity) else {
+        return;
+    };
+
+    let PageLoad { finished, url } = trigger.event();
+    if !finished {
+        *load_state = WebViewLoadState::Loading { url: url.clone() };
+        commands.trigger_targets(PageLoadStarted { url: url.clone() }, entity);
+        if is_ready {
+            commands.entity(entity).remove::<Ready>();
+        }
+        return;
+    }
+
+    *load_state = WebViewLoadState::Loaded { url: url.clone() };
+    commands.trigger_targets(PageLoadFinished { url: url.clone() }, entity);
+    if *ready_signal == ReadySignal::PageLoad && !is_ready {
+        commands.entity(entity).insert(Ready);
+        commands.trigger_targets(WebViewReady { version: None }, entity);
+    }
+}
+
+/// Load changed [Source] in existing webviews.
+pub fn navigate_webviews(
+    webviews: NonSend<WebViews>,
+    sources: Query<(Entity, &Source, Ref<Initialized>), Changed<Source>>,
+) {
+    for (entity, source, initialized) in sources.iter() {
+        // Webview created this frame has already loaded its source
+        if initialized.is_added() {
+            continue;
+        }
 
-        commands
-            .entity(entity)
-            .insert(Initialized)
-            .insert(in_bus)
-            .insert(OutMessageBus::default());
+        let Some(webview) = webviews.get_webview(entity) else {
+            continue;
+        };
+
+        let result = match source {
+            Source::Url(url) => webview.load_url(url),
+            Source::Html(html) => webview.load_html(html),
+            Source::Asset(path) => webview.load_url(&asset_url(path)),
+            Source::Bundle { bundle, path } => webview.load_url(&bundle_url(bundle, path)),
+        };
+        if let Err(e) = result {
+            warn!("Failed to navigate webview {entity}: {e}");
+        }
+    }
+}
+
+/// Copy changed [NavigationPolicy] to the navigation handler. Removed policy allows all urls.
+pub(crate) fn sync_navigation_policies(
+    policies: Query<(&NavigationPolicy, &SharedNavigationPolicy), Changed<NavigationPolicy>>,
+    shared_policies: Query<&SharedNavigationPolicy>,
+    mut removed: RemovedComponents<NavigationPolicy>,
+) {
+    for (policy, shared) in policies.iter() {
+        shared.set(policy.clone());
+    }
+
+    for entity in removed.read() {
+        if let Ok(shared) = shared_policies.get(entity) {
+            shared.set(NavigationPolicy::default());
+        }
     }
 }
 
@@ -70,18 +271,25 @@ pub fn keep_webviews_in_bounds(
     webviews: NonSendMut<WebViews>,
     primary_window_entity: Query<Entity, With<PrimaryWindow>>,
     winit_windows: NonSend<WinitWindows>,
-    webview_entities: Query<(&WebViewComponent, &Position, &Size, &Anchor), With<Initialized>>,
+    webview_entities: Query<(Entity, &Position, &Size, &Anchor), With<Initialized>>,
 ) {
-    let primary_window = primary_window_entity.single();
-    let winit_window = winit_windows.get_window(primary_window).unwrap();
+    let Ok(primary_window) = primary_window_entity.get_single() else {
+        return;
+    };
+    let Some(winit_window) = winit_windows.get_window(primary_window) else {
+        return;
+    };
+
     let scale_factor = winit_window.scale_factor();
     let window_size = winit_window.inner_size();
-    for (webview_component, position, size, anchor) in webview_entities.iter() {
-        let WebViewComponent { webview_name } = webview_component;
-        let webview = webviews
-            .get_webview(webview_name)
-            .expect("WebView with 'Initialized' component should be present in WebViews resource");
+    for (entity, position, size, anchor) in webview_entities.iter() {
+        let Some(webview) = webviews.get_webview(entity) else {
+            continue;
+        };
+
         let bounds = to_webview_bounds(*anchor, position.0, size.0, window_size, scale_factor);
-        webview.set_bounds(bounds).unwrap();
+        if let Err(e) = webview.set_bounds(bounds) {
+            warn!("Failed to update bounds of webview {entity}: {e}");
+        }
     }
 }
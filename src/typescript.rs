@@ -0,0 +1,527 @@
+//! TypeScript declarations for events registered with bevy_wry.
+//!
+//! Types are described using reflection, so every registered type has to derive [Reflect]
+//! and be registered with [App::register_type].
+//! Serde attributes (e.g. `rename`, `tag`) are not taken into account - declarations
+//! follow the default serde representation.
+//! Types are declared with their short names, types sharing a short name with an already
+//! declared type are prefixed with their module path, e.g. `my_game_inventory_Item`.
+use std::any::TypeId;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::path::Path;
+
+use bevy::ecs::reflect::AppTypeRegistry;
+use bevy::prelude::*;
+use bevy::reflect::{TypeInfo, TypeRegistry, VariantInfo};
+
+/// How a registered type is exchanged with [wry::WebView].
+#[derive(Debug, Clone)]
+pub enum WryTypeKind {
+    /// Sent from the page with `bevy.send`
+    Incoming,
+    /// Received in the page with `bevy.on`
+    Outgoing,
+    /// Sent from the page with `bevy.invoke`, answered with `response`
+    Request {
+        response: TypeId,
+        response_name: &'static str,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct WryType {
+    pub tag: String,
+    pub kind: WryTypeKind,
+    pub type_id: TypeId,
+    pub type_name: &'static str,
+}
+
+/// [Resource] listing every type registered with `register_*` functions.
+#[derive(Resource, Default, Debug)]
+pub struct WryTypes {
+    types: Vec<WryType>,
+}
+
+impl WryTypes {
+    pub(crate) fn push<T: 'static>(&mut self, tag: String, kind: WryTypeKind) {
+        self.types.push(WryType {
+            tag,
+            kind,
+            type_id: TypeId::of::<T>(),
+            type_name: std::any::type_name::<T>(),
+        });
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &WryType> {
+        self.types.iter()
+    }
+}
+
+/// Generate `.d.ts` declarations of all registered event and request types, together
+/// with typed `bevy.send`, `bevy.on` and `bevy.invoke` helpers.
+///
+/// Example
+/// ```rust,no_run
+/// use bevy::prelude::*;
+/// use bevy_wry::typescript::write_typescript_declarations;
+/// use bevy_wry::{register_incoming_event, BevyWryPlugin};
+///
+/// #[derive(Event, Reflect, serde::Deserialize)]
+/// enum InCommand {
+///     Rotate { angle: f32 },
+///     Exit,
+/// }
+///
+/// let mut app = App::new();
+/// app.add_plugins(BevyWryPlugin::new(|app| {
+///     register_incoming_event::<InCommand>(app);
+/// }))
+/// .register_type::<InCommand>();
+/// write_typescript_declarations(app.world(), "ui/src/bevy.d.ts").unwrap();
+/// ```
+pub fn typescript_declarations(world: &World) -> String {
+    let Some(wry_types) = world.get_resource::<WryTypes>() else {
+        return Generator::finish(BTreeMap::new(), "", "", "");
+    };
+
+    let registry = world.resource::<AppTypeRegistry>().read();
+    let mut generator = Generator::new(&registry);
+    let mut incoming = String::new();
+    let mut outgoing = String::new();
+    let mut requests = String::new();
+    for wry_type in wry_types.iter() {
+        let tag = serde_json::Value::from(wry_type.tag.as_str());
+        let ts_type = generator.ts_type(None, wry_type.type_id, wry_type.type_name);
+        match &wry_type.kind {
+            WryTypeKind::Incoming => {
+                let _ = writeln!(incoming, "  {tag}: {ts_type};");
+            }
+            WryTypeKind::Outgoing => {
+                let _ = writeln!(outgoing, "  {tag}: {ts_type};");
+            }
+            WryTypeKind::Request {
+                response,
+                response_name,
+            } => {
+                let response = generator.ts_type(None, *response, response_name);
+                let _ = writeln!(
+                    requests,
+                    "  {tag}: {{ request: {ts_type}; response: {response} }};"
+                );
+            }
+        }
+    }
+
+    Generator::finish(generator.declarations, &incoming, &outgoing, &requests)
+}
+
+/// Write [typescript_declarations] to `path`.
+pub fn write_typescript_declarations(world: &World, path: impl AsRef<Path>) -> std::io::Result<()> {
+    std::fs::write(path, typescript_declarations(world))
+}
+
+struct Generator<'a> {
+    registry: &'a TypeRegistry,
+    /// Named declarations, by TS name
+    declarations: BTreeMap<String, String>,
+    /// TS names of declared types
+    names: HashMap<TypeId, String>,
+}
+
+impl<'a> Generator<'a> {
+    fn new(registry: &'a TypeRegistry) -> Self {
+        Self {
+            registry,
+            declarations: BTreeMap::new(),
+            names: HashMap::new(),
+        }
+    }
+
+    /// TS type expression of the Rust type. Structs and enums are declared as named types.
+    fn ts_type(
+        &mut self,
+        type_info: Option<&'static TypeInfo>,
+        type_id: TypeId,
+        type_path: &str,
+    ) -> String {
+        if let Some(primitive) = primitive(type_path) {
+            return primitive.to_string();
+        }
+
+        let Some(type_info) = type_info.or_else(|| self.registry.get_type_info(type_id)) else {
+            return format!("unknown /* {type_path} is not registered */");
+        };
+
+        match type_info {
+            TypeInfo::Enum(info) if info.type_path().starts_with("core::option::Option<") => {
+                let inner = match info.variant("Some") {
+                    Some(VariantInfo::Tuple(some)) => some
+                        .field_at(0)
+                        .map(|field| {
+                            self.ts_type(field.type_info(), field.type_id(), field.type_path())
+                        })
+                        .unwrap_or_else(|| "unknown".to_string()),
+                    _ => "unknown".to_string(),
+                };
+                format!("{inner} | null")
+            }
+            TypeInfo::Struct(_) | TypeInfo::TupleStruct(_) | TypeInfo::Enum(_) => {
+                self.declare(type_info)
+            }
+            TypeInfo::Tuple(info) => {
+                if info.field_len() == 0 {
+                    return "null".to_string();
+                }
+
+                let fields: Vec<String> = info
+                    .iter()
+                    .map(|field| {
+                        self.ts_type(field.type_info(), field.type_id(), field.type_path())
+                    })
+                    .collect();
+                format!("[{}]", fields.join(", "))
+            }
+            TypeInfo::List(info) => {
+                let item =
+                    self.ts_type(info.item_info(), info.item_ty().id(), info.item_ty().path());
+                format!("Array<{item}>")
+            }
+            TypeInfo::Array(info) => {
+                let item =
+                    self.ts_type(info.item_info(), info.item_ty().id(), info.item_ty().path());
+                format!("Array<{item}>")
+            }
+            TypeInfo::Set(info) => {
+                let value = self.ts_type(None, info.value_ty().id(), info.value_ty().path());
+                format!("Array<{value}>")
+            }
+            TypeInfo::Map(info) => {
+                let value = self.ts_type(
+                    info.value_info(),
+                    info.value_ty().id(),
+                    info.value_ty().path(),
+                );
+                format!("Record<string, {value}>")
+            }
+            TypeInfo::Opaque(_) => format!("unknown /* {type_path} */"),
+        }
+    }
+
+    /// Declare struct or enum as named type and return its name.
+    fn declare(&mut self, type_info: &'static TypeInfo) -> String {
+        if let Some(name) = self.names.get(&type_info.type_id()) {
+            return name.clone();
+        }
+
+        let name = self.unique_name(type_info);
+        self.names.insert(type_info.type_id(), name.clone());
+        let body = match type_info {
+            TypeInfo::Struct(info) => {
+                // Reflection doesn't tell unit structs (`null`) from `struct Empty {}` (`{}`)
+                if info.field_len() == 0 {
+                    "Record<string, never> | null".to_string()
+                } else {
+                    let fields: Vec<String> = info
+                        .iter()
+                        .map(|field| {
+                            let ts_type =
+                                self.ts_type(field.type_info(), field.type_id(), field.type_path());
+                            format!("{}: {ts_type}", field.name())
+                        })
+                        .collect();
+                    format!("{{ {} }}", fields.join("; "))
+                }
+            }
+            TypeInfo::TupleStruct(info) => {
+                let fields: Vec<String> = info
+                    .iter()
+                    .map(|field| {
+                        self.ts_type(field.type_info(), field.type_id(), field.type_path())
+                    })
+                    .collect();
+                match fields.len() {
+                    // Newtype structs are serialized as the inner value
+                    1 => fields[0].clone(),
+                    _ => format!("[{}]", fields.join(", ")),
+                }
+            }
+            TypeInfo::Enum(info) => {
+                let variants: Vec<String> =
+                    info.iter().map(|variant| self.variant(variant)).collect();
+                if variants.is_empty() {
+                    "never".to_string()
+                } else {
+                    variants.join("\n  | ")
+                }
+            }
+            _ => unreachable!("only structs and enums are declared"),
+        };
+
+        self.declarations.insert(name.clone(), body);
+        name
+    }
+
+    /// Short name of the type, or its full path if the short name is already taken.
+    fn unique_name(&self, type_info: &TypeInfo) -> String {
+        let is_taken = |name: &str| self.names.values().any(|taken| taken == name);
+        let short_name = ts_name(type_info.ty().short_path());
+        if !is_taken(&short_name) {
+            return short_name;
+        }
+
+        let full_name = ts_name(type_info.type_path());
+        if !is_taken(&full_name) {
+            return full_name;
+        }
+
+        (2..)
+            .map(|i| format!("{full_name}{i}"))
+            .find(|name| !is_taken(name))
+            .unwrap()
+    }
+
+    /// Externally tagged enum variant, matching default serde representation.
+    fn variant(&mut self, variant: &VariantInfo) -> String {
+        match variant {
+            VariantInfo::Unit(info) => format!("\"{}\"", info.name()),
+            VariantInfo::Tuple(info) => {
+                let fields: Vec<String> = info
+                    .iter()
+                    .map(|field| {
+                        self.ts_type(field.type_info(), field.type_id(), field.type_path())
+                    })
+                    .collect();
+                let value = match fields.len() {
+                    1 => fields[0].clone(),
+                    _ => format!("[{}]", fields.join(", ")),
+                };
+                format!("{{ {}: {value} }}", info.name())
+            }
+            VariantInfo::Struct(info) => {
+                let fields: Vec<String> = info
+                    .iter()
+                    .map(|field| {
+                        let ts_type =
+                            self.ts_type(field.type_info(), field.type_id(), field.type_path());
+                        format!("{}: {ts_type}", field.name())
+                    })
+                    .collect();
+                format!("{{ {}: {{ {} }} }}", info.name(), fields.join("; "))
+            }
+        }
+    }
+
+    fn finish(
+        declarations: BTreeMap<String, String>,
+        incoming: &str,
+        outgoing: &str,
+        requests: &str,
+    ) -> String {
+        let mut out = String::from("// Generated by bevy_wry. Do not edit.\n\n");
+        for (name, body) in declarations {
+            let _ = writeln!(out, "export type {name} =\n  {body};\n");
+        }
+
+        let _ = write!(
+            out,
+            "\
+/** Events sent to Bevy with `bevy.send`. */
+export interface BevyIncomingEvents {{
+{incoming}}}
+
+/** Events received from Bevy with `bevy.on`. */
+export interface BevyOutgoingEvents {{
+{outgoing}}}
+
+/** Requests sent to Bevy with `bevy.invoke`. */
+export interface BevyRequests {{
+{requests}}}
+
+export interface Bevy {{
+  readonly version: string;
+  send<K extends keyof BevyIncomingEvents>(type: K, payload: BevyIncomingEvents[K]): void;
+  on<K extends keyof BevyOutgoingEvents>(
+    type: K,
+    listener: (payload: BevyOutgoingEvents[K]) => void,
+  ): () => void;
+  off<K extends keyof BevyOutgoingEvents>(
+    type: K,
+    listener: (payload: BevyOutgoingEvents[K]) => void,
+  ): void;
+  invoke<K extends keyof BevyRequests>(
+    type: K,
+    payload: BevyRequests[K][\"request\"],
+  ): Promise<BevyRequests[K][\"response\"]>;
+  handle(name: string, handler: (args: any) => unknown): void;
+  ready(): void;
+  reportError(error: unknown, source?: string, line?: number): void;
+}}
+
+declare global {{
+  var bevy: Bevy;
+}}
+"
+        );
+        out
+    }
+}
+
+fn primitive(type_path: &str) -> Option<&'static str> {
+    let primitive = match type_path {
+        "bool" => "boolean",
+        "u8" | "u16" | "u32" | "u64" | "u128" | "usize" | "i8" | "i16" | "i32" | "i64" | "i128"
+        | "isize" | "f32" | "f64" => "number",
+        "char"
+        | "str"
+        | "&str"
+        | "alloc::string::String"
+        | "std::path::PathBuf"
+        | "alloc::borrow::Cow<str>" => "string",
+        "()" => "null",
+        _ => return None,
+    };
+    Some(primitive)
+}
+
+fn ts_name(type_path: &str) -> String {
+    let mut name: String = type_path
+        .replace("::", "_")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    while name.ends_with('_') {
+        name.pop();
+    }
+
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::reflect::Typed;
+    use bevy::utils::HashMap;
+
+    use super::*;
+
+    #[derive(Reflect)]
+    struct Player {
+        name: String,
+        health: f32,
+        weapon: Option<u32>,
+        inventory: Vec<Item>,
+    }
+
+    #[derive(Reflect)]
+    struct Item {
+        id: u32,
+    }
+
+    #[derive(Reflect)]
+    struct Empty;
+
+    #[derive(Reflect)]
+    struct Slot(u32);
+
+    #[derive(Reflect)]
+    struct Position(f32, f32);
+
+    #[derive(Reflect)]
+    enum Command {
+        Exit,
+        Rotate(f32),
+        Move(f32, f32),
+        Jump { height: f32 },
+    }
+
+    #[derive(Reflect)]
+    struct Stats {
+        scores: HashMap<String, u32>,
+    }
+
+    mod shop {
+        use bevy::prelude::*;
+
+        #[derive(Reflect)]
+        pub struct Item {
+            pub price: u32,
+        }
+    }
+
+    #[derive(Reflect)]
+    struct Trade {
+        sold: Item,
+        bought: shop::Item,
+    }
+
+    fn declarations<T: Typed>() -> (String, BTreeMap<String, String>) {
+        let registry = TypeRegistry::default();
+        let mut generator = Generator::new(&registry);
+        let ts_type = generator.ts_type(Some(T::type_info()), TypeId::of::<T>(), T::type_path());
+        (ts_type, generator.declarations)
+    }
+
+    fn declaration<T: Typed>() -> String {
+        let (name, declarations) = declarations::<T>();
+        declarations[&name].clone()
+    }
+
+    #[test]
+    fn structs() {
+        let (name, declarations) = declarations::<Player>();
+        assert_eq!(name, "Player");
+        assert_eq!(
+            declarations["Player"],
+            "{ name: string; health: number; weapon: number | null; inventory: Array<Item> }"
+        );
+        assert_eq!(declarations["Item"], "{ id: number }");
+        assert_eq!(declaration::<Empty>(), "Record<string, never> | null");
+        assert_eq!(declaration::<Slot>(), "number");
+        assert_eq!(declaration::<Position>(), "[number, number]");
+    }
+
+    #[test]
+    fn enum_variants() {
+        assert_eq!(
+            declaration::<Command>(),
+            "\"Exit\"\n  | { Rotate: number }\n  | { Move: [number, number] }\n  | { Jump: { height: number } }"
+        );
+    }
+
+    #[test]
+    fn options_and_maps() {
+        let (ts_type, _) = declarations::<Option<Item>>();
+        assert_eq!(ts_type, "Item | null");
+        assert_eq!(declaration::<Stats>(), "{ scores: Record<string, number> }");
+    }
+
+    #[test]
+    fn bevy_interface_declares_client_members() {
+        let declarations = Generator::finish(BTreeMap::new(), "", "", "");
+        for member in [
+            "readonly version: string;",
+            "send<K",
+            "on<K",
+            "off<K",
+            "invoke<K",
+            "handle(name: string",
+            "ready(): void;",
+            "reportError(error: unknown",
+        ] {
+            assert!(declarations.contains(member), "{member}");
+        }
+    }
+
+    #[test]
+    fn colliding_names_are_disambiguated() {
+        let (_, declarations) = declarations::<Trade>();
+        let shop_item = ts_name(shop::Item::type_path());
+        assert_eq!(
+            declarations["Trade"],
+            format!("{{ sold: Item; bought: {shop_item} }}")
+        );
+        assert_eq!(declarations["Item"], "{ id: number }");
+        assert_eq!(declarations[&shop_item], "{ price: number }");
+        assert!(shop_item.ends_with("tests_shop_Item"));
+    }
+}